@@ -0,0 +1,47 @@
+//! Compares the rule backends: run once as is for the generated checkers and
+//! once with `--features rule_table` for the rule table.
+#[macro_use]
+extern crate bencher;
+use bencher::Bencher;
+
+mod common;
+
+static FIXTURES: &[&[u8]] = &[
+    include_bytes!("image/gif"),
+    include_bytes!("image/png"),
+    include_bytes!("application/zip"),
+    include_bytes!("application/x-7z-compressed"),
+    include_bytes!("text/plain"),
+];
+
+/// Every fixture in turn
+fn from_u8_fixtures(b: &mut Bencher) {
+    let octet_stream = common::octet_stream();
+    b.iter(|| {
+        FIXTURES
+            .iter()
+            .copied()
+            .chain([&octet_stream[..]])
+            .map(|bytes| tika_magic::from_u8(bytes))
+            .collect::<Vec<_>>()
+    });
+}
+
+/// Bytes no rule matches, so every plausible type is checked
+fn from_u8_octet_stream(b: &mut Bencher) {
+    let bytes = common::octet_stream();
+    b.iter(|| tika_magic::from_u8(&bytes));
+}
+
+/// Text, which goes through the range and root-XML rules
+fn from_u8_text(b: &mut Bencher) {
+    b.iter(|| tika_magic::from_u8(include_bytes!("text/plain")));
+}
+
+benchmark_group!(
+    benches,
+    from_u8_fixtures,
+    from_u8_octet_stream,
+    from_u8_text
+);
+benchmark_main!(benches);
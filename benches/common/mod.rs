@@ -0,0 +1,13 @@
+/// 4 KiB of bytes no rule matches, from a xorshift generator with a fixed seed so
+/// every run checks the same input
+pub fn octet_stream() -> Vec<u8> {
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    (0..4096)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state >> 32) as u8
+        })
+        .collect()
+}
@@ -0,0 +1,26 @@
+#[macro_use]
+extern crate bencher;
+use bencher::Bencher;
+
+const UPLOADS: &[&str] = &["image/png", "image/jpeg", "image/gif", "application/pdf"];
+
+///Image tests
+fn image_gif(b: &mut Bencher) {
+    b.iter(|| tika_magic::detect_among(include_bytes!("image/gif"), UPLOADS));
+}
+fn image_png(b: &mut Bencher) {
+    b.iter(|| tika_magic::detect_among(include_bytes!("image/png"), UPLOADS));
+}
+
+/// Archive tests
+fn application_zip(b: &mut Bencher) {
+    b.iter(|| tika_magic::detect_among(include_bytes!("application/zip"), UPLOADS));
+}
+
+/// Text tests
+fn text_plain(b: &mut Bencher) {
+    b.iter(|| tika_magic::detect_among(include_bytes!("text/plain"), UPLOADS));
+}
+
+benchmark_group!(benches, image_gif, image_png, application_zip, text_plain);
+benchmark_main!(benches);
@@ -2,6 +2,8 @@
 extern crate bencher;
 use bencher::Bencher;
 
+mod common;
+
 ///Image tests
 fn image_gif(b: &mut Bencher) {
     b.iter(|| tika_magic::from_u8(include_bytes!("image/gif")));
@@ -14,11 +16,22 @@ fn image_png(b: &mut Bencher) {
 fn application_zip(b: &mut Bencher) {
     b.iter(|| tika_magic::from_u8(include_bytes!("application/zip")));
 }
+fn application_octet_stream(b: &mut Bencher) {
+    let bytes = common::octet_stream();
+    b.iter(|| tika_magic::from_u8(&bytes));
+}
 
 /// Text tests
 fn text_plain(b: &mut Bencher) {
     b.iter(|| tika_magic::from_u8(include_bytes!("text/plain")));
 }
 
-benchmark_group!(benches, image_gif, image_png, application_zip, text_plain);
+benchmark_group!(
+    benches,
+    image_gif,
+    image_png,
+    application_zip,
+    application_octet_stream,
+    text_plain
+);
 benchmark_main!(benches);
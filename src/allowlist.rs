@@ -0,0 +1,227 @@
+use crate::detector::DEFAULT_DETECTOR;
+use crate::magic::{MimeTypeChecker, RangeHits};
+use crate::{
+    check_recursive, is_a, maybe_open_ole, maybe_open_zip, registry, Detector, Mime, PipelineStage,
+};
+
+/// A container special handler: the type every result of it is a subtype of, the
+/// magic it looks for and the types it can find besides subtypes of the root
+struct Container {
+    root: Mime,
+    magic: &'static [u8],
+    types: &'static [Mime],
+}
+
+const ZIP: Container = Container {
+    root: "application/zip",
+    magic: crate::magic::ZipSpecialHandler::MAGIC,
+    types: crate::magic::ZipSpecialHandler::TYPES,
+};
+
+const OLE: Container = Container {
+    root: "application/x-tika-msoffice",
+    magic: crate::magic::OleSpecialHandler::MAGIC,
+    types: crate::magic::OleSpecialHandler::TYPES,
+};
+
+/// Whether a container handler can find one of the allowed types in the bytes.
+///
+/// The handler's own types are compared by name as well, as some of them, like
+/// `application/vnd.openxmlformats-officedocument`, aren't in the database.
+fn needs_container(
+    names: &[&str],
+    allowed: &[&'static dyn MimeTypeChecker],
+    container: &Container,
+    bytes: &[u8],
+) -> bool {
+    bytes.starts_with(container.magic)
+        && (container.types.iter().any(|mime| allows(names, mime))
+            || allowed.iter().any(|checker| {
+                let mime = checker.get_mime();
+                mime != container.root && is_a(mime, container.root)
+            }))
+}
+
+/// Whether a type is one of the allowed names
+fn allows(names: &[&str], mime: Mime) -> bool {
+    names.iter().any(|name| name.eq_ignore_ascii_case(mime))
+}
+
+/// The most specific allowed type a detected type is, ties going to the higher
+/// magic priority and then to the earlier allowed type
+fn best(allowed: &[&'static dyn MimeTypeChecker], found: &[Mime]) -> Option<Mime> {
+    // Any other allowed type it is would be a supertype of it
+    if let [mime] = found {
+        if allowed.iter().any(|checker| checker.get_mime() == *mime) {
+            return Some(mime);
+        }
+    }
+
+    let mut matched = allowed
+        .iter()
+        .filter(|checker| found.iter().any(|mime| is_a(mime, checker.get_mime())))
+        .collect::<Vec<_>>();
+    matched.sort_by_key(|checker| std::cmp::Reverse(checker.get_priority()));
+
+    matched
+        .iter()
+        .find(|checker| {
+            let mime = checker.get_mime();
+            !matched
+                .iter()
+                .any(|other| other.get_mime() != mime && is_a(other.get_mime(), mime))
+        })
+        .map(|checker| checker.get_mime())
+}
+
+impl Detector {
+    /// Gets which of the allowed MIME types a byte stream is, checking only those.
+    ///
+    /// See [`detect_among`](crate::detect_among).
+    pub fn detect_among(&self, bytes: &[u8], allowed: &[&str]) -> Option<Mime> {
+        let custom = self.custom();
+        if let Some(mime) = allowed.iter().find_map(|m| custom.find_match(m, bytes)) {
+            return Some(mime);
+        }
+
+        let names = allowed;
+        let allowed = names
+            .iter()
+            .filter_map(|mimetype| registry::resolve(mimetype))
+            .collect::<Vec<_>>();
+
+        if self.pipeline().contains(&PipelineStage::Container) {
+            let container = if self.open_zips() && needs_container(names, &allowed, &ZIP, bytes) {
+                maybe_open_zip(bytes)
+            } else {
+                None
+            }
+            .or_else(|| {
+                if self.open_ole() && needs_container(names, &allowed, &OLE, bytes) {
+                    maybe_open_ole(bytes)
+                } else {
+                    None
+                }
+            });
+
+            let container = container.and_then(|mime| {
+                best(&allowed, &[mime]).or_else(|| allows(names, mime).then_some(mime))
+            });
+            if let Some(mime) = container {
+                return Some(mime);
+            }
+        }
+
+        let hits = RangeHits::new(bytes);
+        let mut found = allowed
+            .iter()
+            .filter_map(|checker| check_recursive(*checker, bytes, &hits))
+            .collect::<Vec<_>>();
+        found.dedup();
+
+        best(&allowed, &found)
+    }
+}
+
+/// Gets which of the allowed MIME types a byte stream is, checking only those.
+///
+/// Meant for endpoints that accept a handful of types: rather than checking every
+/// type in the database like [`from_u8`](crate::from_u8), only the allowed types
+/// and their subtypes are checked, and a ZIP archive or OLE compound document is
+/// only opened when a type found inside it is allowed. That makes it much faster.
+///
+/// Returns the most specific allowed type the bytes are, which may be a supertype
+/// of what `from_u8` detects, like `application/zip` for a DOCX when only ZIP
+/// archives are allowed. Returns None if they are none of the allowed types. Aliases
+/// are accepted, unknown types are ignored.
+///
+/// # Examples
+/// ```rust
+/// // Load a GIF file
+/// let input: &[u8] = include_bytes!("../tests/inputs/image/gif/gif.gif");
+///
+/// let images = ["image/png", "image/gif", "image/jpeg"];
+/// assert_eq!(tika_magic::detect_among(input, &images), Some("image/gif"));
+/// assert_eq!(tika_magic::detect_among(input, &["application/pdf"]), None);
+/// ```
+pub fn detect_among(bytes: &[u8], allowed: &[&str]) -> Option<Mime> {
+    DEFAULT_DETECTOR.detect_among(bytes, allowed)
+}
+
+#[cfg(all(test, feature = "all_types"))]
+mod tests {
+    use super::*;
+
+    const UPLOADS: &[&str] = &["image/png", "image/jpeg", "application/pdf"];
+
+    #[test]
+    fn test_detect_among() {
+        let png = include_bytes!("../tests/inputs/image/png/png.png");
+        let gif = include_bytes!("../tests/inputs/image/gif/gif.gif");
+        assert_eq!(detect_among(png, UPLOADS), Some("image/png"));
+        assert_eq!(detect_among(gif, UPLOADS), None);
+        assert_eq!(detect_among(png, &[]), None);
+        assert_eq!(
+            detect_among(png, &["image/x-made-up", "IMAGE/PNG"]),
+            Some("image/png")
+        );
+    }
+
+    #[test]
+    fn test_most_specific() {
+        let svg = include_bytes!("../tests/inputs/image/svg+xml/svg.svg");
+        assert_eq!(
+            detect_among(svg, &["text/xml", "image/svg+xml"]),
+            Some("image/svg+xml")
+        );
+        assert_eq!(detect_among(svg, &["text/xml"]), Some("application/xml"));
+    }
+
+    #[test]
+    fn test_agrees_with_from_u8() {
+        use std::fs;
+        use std::path::Path;
+
+        fn visit(dir: &Path, f: &mut dyn FnMut(&Path, &[u8])) {
+            for entry in fs::read_dir(dir).unwrap() {
+                let path = entry.unwrap().path();
+                if path.is_dir() {
+                    visit(&path, f);
+                } else {
+                    f(&path, &fs::read(&path).unwrap());
+                }
+            }
+        }
+
+        let inputs = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/inputs");
+        visit(&inputs, &mut |path, input| {
+            let mime = crate::from_u8(input);
+            // The fallback is what from_u8 returns when no type matched
+            if mime != "application/octet-stream" {
+                assert_eq!(
+                    detect_among(input, &[mime, "image/gif"]),
+                    Some(mime),
+                    "{path:?}"
+                );
+            }
+        });
+    }
+
+    #[cfg(feature = "open_zips")]
+    #[test]
+    fn test_containers() {
+        let odt = include_bytes!(
+            "../tests/inputs/application/vnd.oasis.opendocument.text/testODTStyles3.odt"
+        );
+        let odt_type = "application/vnd.oasis.opendocument.text";
+        assert_eq!(detect_among(odt, &[odt_type]), Some(odt_type));
+        assert_eq!(
+            detect_among(odt, &["application/zip"]),
+            Some("application/zip")
+        );
+        assert_eq!(detect_among(odt, &["application/pdf"]), None);
+
+        let detector = Detector::builder().open_zips(false).build().unwrap();
+        assert_eq!(detector.detect_among(odt, &[odt_type]), None);
+    }
+}
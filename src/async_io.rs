@@ -0,0 +1,249 @@
+use crate::detector::{open_containers, DEFAULT_DETECTOR};
+use crate::{Detector, Mime, HEAD_SIZE};
+use std::io;
+use std::panic;
+use std::path::Path;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// How much is read before checking whether the rest of the input could still
+/// change the type, the amount growing fourfold after each check
+const FIRST_CHECK: usize = 1024;
+
+/// Reads up to `max_bytes` without blocking, retrying short reads, and stops early
+/// once the rest of the input could no longer change the type. Also tells whether
+/// the head is the whole input.
+async fn read_head<R: AsyncRead + Unpin>(
+    detector: &Detector,
+    mut reader: R,
+) -> io::Result<(Vec<u8>, bool)> {
+    let limit = detector.max_bytes();
+    let mut head = Vec::with_capacity(limit.min(HEAD_SIZE));
+    let mut check = FIRST_CHECK;
+    loop {
+        let end = check.min(limit);
+        (&mut reader)
+            .take((end - head.len()) as u64)
+            .read_to_end(&mut head)
+            .await?;
+        if head.len() < end {
+            return Ok((head, true));
+        }
+        if end == limit || detector.is_settled(&head) {
+            return Ok((head, false));
+        }
+        check *= 4;
+    }
+}
+
+impl Detector {
+    /// Gets the MIME type of the data read from an async reader.
+    ///
+    /// See [`from_async_reader`](crate::from_async_reader).
+    pub async fn from_async_reader<R: AsyncRead + Unpin>(&self, reader: R) -> Option<Mime> {
+        let (head, whole) = read_head(self, reader).await.ok()?;
+        if head.is_empty() {
+            return None;
+        }
+
+        // Opening a ZIP or OLE container inflates and parses its entries, which would
+        // hold up the other tasks of the runtime thread. The rest of the detection
+        // only checks the head, so it runs here.
+        let (head, container) = if self.may_open_container(&head) {
+            let (open_zips, open_ole) = (self.open_zips(), self.open_ole());
+            let opened = tokio::task::spawn_blocking(move || {
+                let container = open_containers(&head, open_zips, open_ole);
+                (head, container)
+            });
+            match opened.await {
+                Ok(opened) => opened,
+                Err(error) if error.is_panic() => panic::resume_unwind(error.into_panic()),
+                Err(_) => return None,
+            }
+        } else {
+            (head, None)
+        };
+
+        Some(self.detect_opened(&head, whole, container))
+    }
+
+    /// Gets the MIME type for a path without blocking the runtime.
+    ///
+    /// See [`from_async_path`](crate::from_async_path).
+    pub async fn from_async_path(&self, path: &Path) -> Option<Mime> {
+        let file = tokio::fs::File::open(path).await.ok()?;
+        self.from_async_reader(file).await
+    }
+}
+
+/// Gets the MIME type of the data read from a tokio [`AsyncRead`].
+///
+/// Reads up to the first 128 KiB, like [`from_reader`](crate::from_reader), and
+/// stops there, so an upload can be checked before the rest of it arrives. As it
+/// reads, it checks whether the rest could still change the type, and stops as soon
+/// as it can't: when a type is sure to match and every type checked before it is
+/// sure not to. A rule looking anywhere in the head, like a regex with no range,
+/// keeps it reading, and so does a ZIP or OLE container, as the directory that tells
+/// their formats apart may be anywhere in them. Returns None if nothing could be
+/// read.
+///
+/// Opening a ZIP or OLE container runs on the blocking thread pool of the tokio
+/// runtime it is called from.
+///
+/// Requires the `tokio` feature.
+///
+/// # Examples
+/// ```rust
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// // Read a GIF file from memory
+/// let input: &[u8] = include_bytes!("../tests/inputs/image/gif/gif.gif");
+///
+/// // Find the MIME type of the GIF
+/// let result = tika_magic::from_async_reader(input).await;
+/// assert_eq!(result, Some("image/gif"));
+/// # }
+/// ```
+pub async fn from_async_reader<R: AsyncRead + Unpin>(reader: R) -> Option<Mime> {
+    DEFAULT_DETECTOR.from_async_reader(reader).await
+}
+
+/// Gets the MIME type for a path without blocking the runtime.
+///
+/// The file is opened and read with `tokio::fs`, rather than `std::fs` as
+/// [`from_filepath`](crate::from_filepath) does. Returns None if the file cannot be
+/// opened or is empty.
+///
+/// Requires the `tokio` feature.
+///
+/// # Examples
+/// ```rust
+/// use std::path::Path;
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// // Get path to a GIF file
+/// let path = Path::new("./tests/inputs/image/gif/gif.gif");
+///
+/// // Find the MIME type of the GIF
+/// let result = tika_magic::from_async_path(path).await;
+/// assert_eq!(result, Some("image/gif"));
+/// # }
+/// ```
+pub async fn from_async_path(path: &Path) -> Option<Mime> {
+    DEFAULT_DETECTOR.from_async_path(path).await
+}
+
+#[cfg(all(test, feature = "all_types"))]
+mod tests {
+    use super::*;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+    use tokio::io::ReadBuf;
+
+    /// Hands out one byte per poll, and stays pending every other poll, like a slow
+    /// upload
+    struct Trickle<'a> {
+        data: &'a [u8],
+        ready: bool,
+    }
+
+    impl AsyncRead for Trickle<'_> {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            self.ready = !self.ready;
+            if !self.ready {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+
+            if let Some((byte, rest)) = self.data.split_first() {
+                buf.put_slice(&[*byte]);
+                self.data = rest;
+            }
+
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_from_async_reader_short_reads() {
+        let data = include_bytes!("../tests/inputs/image/png/png.png");
+        let reader = Trickle { data, ready: false };
+        assert_eq!(from_async_reader(reader).await, Some("image/png"));
+
+        let reader = Trickle {
+            data: b"",
+            ready: false,
+        };
+        assert_eq!(from_async_reader(reader).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_from_async_reader_stops_at_the_head() {
+        let data = vec![b' '; HEAD_SIZE * 2];
+        let mut reader = &data[..];
+        assert!(from_async_reader(&mut reader).await.is_some());
+        assert_eq!(reader.len(), HEAD_SIZE);
+    }
+
+    #[tokio::test]
+    async fn test_from_async_reader_stops_once_settled() {
+        let data = include_bytes!("../tests/inputs/image/png/baseball.png");
+        let mut reader = &data[..];
+        assert_eq!(from_async_reader(&mut reader).await, Some("image/png"));
+        assert_eq!(reader.len(), data.len() - FIRST_CHECK);
+    }
+
+    #[tokio::test]
+    async fn test_from_async_reader_agrees_with_from_reader() {
+        use std::fs;
+
+        fn visit(dir: &Path, paths: &mut Vec<std::path::PathBuf>) {
+            for entry in fs::read_dir(dir).unwrap() {
+                let path = entry.unwrap().path();
+                if path.is_dir() {
+                    visit(&path, paths);
+                } else {
+                    paths.push(path);
+                }
+            }
+        }
+
+        let mut paths = Vec::new();
+        visit(Path::new("./tests/inputs"), &mut paths);
+        for path in paths {
+            let input = fs::read(&path).unwrap();
+            assert_eq!(
+                from_async_reader(&input[..]).await,
+                crate::from_reader(&input[..]),
+                "{path:?}"
+            );
+        }
+    }
+
+    #[cfg(feature = "open_zips")]
+    #[tokio::test]
+    async fn test_from_async_reader_opens_containers() {
+        let data = include_bytes!("../tests/inputs/application/vnd.openxmlformats-officedocument.wordprocessingml.document/testWORD.docx");
+        assert_eq!(
+            from_async_reader(&data[..]).await,
+            Some("application/vnd.openxmlformats-officedocument.wordprocessingml.document")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_from_async_path() {
+        let path = Path::new("./tests/inputs/video/mp4/mp4.mp4");
+        assert_eq!(from_async_path(path).await, crate::from_filepath(path));
+        assert_eq!(from_async_path(Path::new("./does/not/exist")).await, None);
+
+        let detector = Detector::builder().max_bytes(4).build().unwrap();
+        assert_eq!(
+            detector.from_async_path(path).await,
+            detector.from_filepath(path)
+        );
+    }
+}
@@ -0,0 +1,365 @@
+use crate::detector::DEFAULT_DETECTOR;
+use crate::explain::Found;
+use crate::magic::{MimeTypeChecker, RangeHits, Rule, MIME_TYPES};
+use crate::{from_filename, registry, Detector, Mime, Stage};
+use std::fmt;
+use std::fs::File;
+use std::path::Path;
+
+/// How a candidate type was found.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+#[non_exhaustive]
+pub enum Source {
+    /// A magic rule on the leading bytes matched.
+    Magic,
+    /// The ZIP or OLE special handler recognized the container contents.
+    Container,
+    /// A `root-XML` rule matched the root element or its namespace.
+    XmlRoot,
+    /// The file name matched one of the type's glob patterns.
+    Glob,
+    /// The text heuristics recognized plain text.
+    Text,
+    /// A type registered on the detector matched.
+    Custom,
+    /// Nothing matched, so the fallback type was returned.
+    Fallback,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Source::Magic => "magic",
+            Source::Container => "container",
+            Source::XmlRoot => "xml-root",
+            Source::Glob => "glob",
+            Source::Text => "text",
+            Source::Custom => "custom",
+            Source::Fallback => "fallback",
+        })
+    }
+}
+
+/// A possible type of a byte stream, see [`from_u8_ranked`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Candidate {
+    #[cfg_attr(
+        feature = "serde",
+        serde(deserialize_with = "crate::serialization::mime")
+    )]
+    mime: Mime,
+    priority: u32,
+    source: Source,
+}
+
+impl Candidate {
+    fn new(mime: Mime, source: Source) -> Self {
+        Candidate {
+            mime,
+            priority: registry::resolve(mime).map_or(0, |checker| checker.get_priority()),
+            source,
+        }
+    }
+
+    /// The type a step of the detection found, tagged with how it was found
+    fn found(found: Found, bytes: &[u8]) -> Self {
+        let source = match found.stage {
+            Stage::ZipContainer | Stage::OleContainer => Source::Container,
+            Stage::XmlRoot => Source::XmlRoot,
+            Stage::Text => Source::Text,
+            Stage::Name => Source::Glob,
+            Stage::Custom => Source::Custom,
+            _ => registry::resolve(found.mime)
+                .map_or(Source::Magic, |checker| magic_source(checker, bytes)),
+        };
+
+        Candidate::new(found.mime, source)
+    }
+
+    /// The candidate type.
+    pub fn mime(&self) -> Mime {
+        self.mime
+    }
+
+    /// The Tika magic priority of the type, from 0 to 100.
+    pub fn priority(&self) -> u32 {
+        self.priority
+    }
+
+    /// How the type was found.
+    pub fn source(&self) -> Source {
+        self.source
+    }
+}
+
+impl fmt::Display for Candidate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({}, priority {})",
+            self.mime, self.source, self.priority
+        )
+    }
+}
+
+/// Tells a `root-XML` match from a plain magic match
+fn magic_source(checker: &'static dyn MimeTypeChecker, bytes: &[u8]) -> Source {
+    let mut hits = vec![];
+    checker.get_rule().explain(bytes, false, &mut hits);
+
+    if hits
+        .iter()
+        .any(|(rule, _)| matches!(rule, Rule::RootXml(..)))
+    {
+        Source::XmlRoot
+    } else {
+        Source::Magic
+    }
+}
+
+/// Puts `leading` ahead of every other match, ordered by priority
+fn rank(bytes: &[u8], whole: bool, leading: Vec<Candidate>, glob: Option<Mime>) -> Vec<Candidate> {
+    let hits = RangeHits::new(bytes).whole(whole);
+    let mut others = MIME_TYPES
+        .iter()
+        .filter(|checker| checker.check_with(bytes, &hits))
+        .map(|checker| Candidate::new(checker.get_mime(), magic_source(*checker, bytes)))
+        .chain(glob.map(|mime| Candidate::new(mime, Source::Glob)))
+        .collect::<Vec<_>>();
+
+    // A stable sort keeps the database order, which puts subtypes before their
+    // parents, for candidates with the same priority
+    others.sort_by_key(|candidate| std::cmp::Reverse(candidate.priority));
+
+    let mut ranked = leading;
+    for candidate in others {
+        if !ranked.iter().any(|c| c.mime == candidate.mime) {
+            ranked.push(candidate);
+        }
+    }
+
+    ranked
+}
+
+impl Detector {
+    /// Gets every MIME type that matches a byte stream, best first.
+    ///
+    /// See [`from_u8_ranked`](crate::from_u8_ranked).
+    pub fn from_u8_ranked(&self, bytes: &[u8]) -> Vec<Candidate> {
+        self.ranked(bytes, false, None)
+    }
+
+    /// Gets every MIME type that matches a byte stream and the name it was stored
+    /// under, best first.
+    ///
+    /// See [`from_u8_ranked_with_name`](crate::from_u8_ranked_with_name).
+    pub fn from_u8_ranked_with_name(&self, bytes: &[u8], name: &str) -> Vec<Candidate> {
+        self.ranked(bytes, false, Some(name))
+    }
+
+    /// Gets every MIME type that matches a file, using its contents and its name,
+    /// best first.
+    ///
+    /// See [`from_filepath_ranked`](crate::from_filepath_ranked).
+    pub fn from_filepath_ranked(&self, path: &Path) -> Option<Vec<Candidate>> {
+        let head = self.read_head(File::open(path).ok()?).ok()?;
+        if head.is_empty() {
+            return None;
+        }
+
+        let name = path.file_name().and_then(|name| name.to_str());
+        Some(self.ranked(&head, self.is_whole(&head), name))
+    }
+
+    /// Ranks the candidates of a head, `whole` telling whether it is the whole input
+    fn ranked(&self, bytes: &[u8], whole: bool, name: Option<&str>) -> Vec<Candidate> {
+        let mut steps = vec![];
+        self.run_recorded(
+            bytes,
+            whole,
+            name,
+            || self.handle_special_files(bytes),
+            &mut |found| steps.push(found),
+        );
+
+        let fallback = Candidate::new(self.fallback(), Source::Fallback);
+        let mut steps = steps
+            .into_iter()
+            .rev()
+            .map(|found| Candidate::found(found, bytes));
+        let mut leading = vec![steps.next().unwrap_or(fallback)];
+        if leading[0].source == Source::Glob {
+            // The contents were overruled by the name, keep them as the runner up
+            leading.push(steps.next().unwrap_or(fallback));
+        }
+
+        rank(bytes, whole, leading, name.and_then(from_filename))
+    }
+}
+
+/// Gets every MIME type that matches a byte stream, best first.
+///
+/// The first candidate is always the type [`from_u8`] returns. The others are
+/// ordered by their Tika magic priority. Each candidate says how it was found, so
+/// a caller can fall back to the next one when the first is not usable.
+///
+/// # Examples
+/// ```rust
+/// use tika_magic::Source;
+///
+/// // Load a MP4 file
+/// let input: &[u8] = include_bytes!("../tests/inputs/video/mp4/mp4.mp4");
+///
+/// let candidates = tika_magic::from_u8_ranked(input);
+/// assert_eq!(candidates[0].mime(), "video/mp4");
+/// assert_eq!(candidates[0].source(), Source::Magic);
+/// assert!(candidates.iter().any(|c| c.mime() == "video/quicktime"));
+/// ```
+pub fn from_u8_ranked(bytes: &[u8]) -> Vec<Candidate> {
+    DEFAULT_DETECTOR.from_u8_ranked(bytes)
+}
+
+/// Gets every MIME type that matches a byte stream and the name it was stored
+/// under, best first.
+///
+/// The first candidate is always the type [`from_u8_with_name`](crate::from_u8_with_name)
+/// returns. The type matching the name is included as a [`Source::Glob`] candidate.
+///
+/// # Examples
+/// ```rust
+/// use tika_magic::Source;
+///
+/// // Load a plain ZIP file
+/// let input: &[u8] = include_bytes!("../tests/inputs/application/zip/zip.zip");
+///
+/// let candidates = tika_magic::from_u8_ranked_with_name(input, "library.jar");
+/// assert_eq!(candidates[0].mime(), "application/java-archive");
+/// assert_eq!(candidates[0].source(), Source::Glob);
+/// assert!(candidates.iter().any(|c| c.mime() == "application/zip"));
+/// ```
+pub fn from_u8_ranked_with_name(bytes: &[u8], name: &str) -> Vec<Candidate> {
+    DEFAULT_DETECTOR.from_u8_ranked_with_name(bytes, name)
+}
+
+/// Gets every MIME type that matches a file, using its contents and its name, best
+/// first. See [`from_u8_ranked_with_name`].
+///
+/// Returns None if the file cannot be read or is empty.
+///
+/// # Examples
+/// ```rust
+/// use std::path::Path;
+///
+/// // Get path to a GIF file
+/// let path = Path::new("./tests/inputs/image/gif/gif.gif");
+///
+/// let candidates = tika_magic::from_filepath_ranked(path).unwrap();
+/// assert_eq!(candidates[0].mime(), "image/gif");
+/// ```
+pub fn from_filepath_ranked(path: &Path) -> Option<Vec<Candidate>> {
+    DEFAULT_DETECTOR.from_filepath_ranked(path)
+}
+
+#[cfg(all(test, feature = "all_types"))]
+mod tests {
+    use super::*;
+    use crate::{from_u8, PipelineStage};
+
+    #[test]
+    fn test_ranked_starts_with_from_u8() {
+        let data = include_bytes!("../tests/inputs/video/mp4/mp4.mp4");
+        let ranked = from_u8_ranked(data);
+        assert_eq!(ranked[0].mime(), from_u8(data));
+        assert!(ranked[1..]
+            .windows(2)
+            .all(|w| w[0].priority() >= w[1].priority()));
+    }
+
+    #[test]
+    fn test_ranked_sources() {
+        let data =
+            include_bytes!("../tests/inputs/application/onix-message+xml/testONIXMessage.xml");
+        let ranked = from_u8_ranked(data);
+        assert_eq!(ranked[0].mime(), "application/onix-message+xml");
+        assert_eq!(ranked[0].source(), Source::XmlRoot);
+        assert!(ranked
+            .iter()
+            .any(|c| c.mime() == "application/xml" && c.source() == Source::Magic));
+
+        let ranked = from_u8_ranked(&[0xfa, 0xce, 0xb0, 0x0c, 0x00, 0x17]);
+        assert_eq!(ranked.len(), 1);
+        assert_eq!(ranked[0].mime(), "application/octet-stream");
+        assert_eq!(ranked[0].source(), Source::Fallback);
+    }
+
+    #[test]
+    fn test_ranked_with_name() {
+        let data = include_bytes!("../tests/inputs/image/png/png.png");
+
+        // The name disagrees with the contents, so it only adds a candidate
+        let ranked = from_u8_ranked_with_name(data, "image.gif");
+        assert_eq!(ranked[0].mime(), "image/png");
+        assert_eq!(ranked[0].source(), Source::Magic);
+        assert!(ranked
+            .iter()
+            .any(|c| c.mime() == "image/gif" && c.source() == Source::Glob));
+
+        let data = include_bytes!("../tests/inputs/application/zip/zip.zip");
+        let ranked = from_u8_ranked_with_name(data, "library.jar");
+        assert_eq!(ranked[0].mime(), "application/java-archive");
+        assert_eq!(ranked[1].mime(), from_u8(data));
+    }
+
+    #[cfg(feature = "open_zips")]
+    #[test]
+    fn test_ranked_container() {
+        let data = include_bytes!("../tests/inputs/application/vnd.openxmlformats-officedocument.wordprocessingml.document/testWORD.docx");
+        let ranked = from_u8_ranked(data);
+        assert_eq!(ranked[0].mime(), from_u8(data));
+        assert_eq!(ranked[0].source(), Source::Container);
+        assert!(ranked.iter().any(|c| c.mime() == "application/zip"));
+    }
+
+    #[test]
+    fn test_detector_ranked() {
+        let path = Path::new("./tests/inputs/application/onix-message+xml/testONIXMessage.xml");
+        let detector = Detector::builder().max_bytes(5).build().unwrap();
+        let ranked = detector.from_filepath_ranked(path).unwrap();
+        assert_eq!(
+            Some(ranked[0].mime()),
+            detector.from_filepath_with_name(path)
+        );
+        assert_eq!(ranked[0].mime(), "application/xml");
+
+        let detector = Detector::builder()
+            .pipeline(&[PipelineStage::Magic, PipelineStage::Text])
+            .fallback("application/x-executable")
+            .build()
+            .unwrap();
+        let ranked = detector.from_u8_ranked(b"a,b,c\n1,2,3\n");
+        assert_eq!(ranked[0].mime(), "text/plain");
+        assert_eq!(ranked[0].source(), Source::Text);
+
+        let ranked = detector.from_u8_ranked(&[0xfa, 0xce, 0xb0, 0x0c, 0x00, 0x17]);
+        assert_eq!(ranked[0].mime(), "application/x-executable");
+        assert_eq!(ranked[0].source(), Source::Fallback);
+    }
+
+    #[cfg(feature = "open_zips")]
+    #[test]
+    fn test_detector_ranked_containers_toggle() {
+        let data = include_bytes!(
+            "../tests/inputs/application/vnd.oasis.opendocument.text/testODTStyles3.odt"
+        );
+        let detector = Detector::builder().open_zips(false).build().unwrap();
+        let ranked = detector.from_u8_ranked(data);
+        assert_eq!(ranked[0].mime(), "application/zip");
+        assert_eq!(ranked[0].source(), Source::Magic);
+    }
+}
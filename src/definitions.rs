@@ -0,0 +1,645 @@
+use crate::magic::*;
+use crate::registry::{intern, intern_type};
+use crate::{Error, MediaType, Mime, TypeDetector};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use regex::bytes::Regex;
+use std::path::Path;
+use std::sync::Arc;
+
+/// A magic rule read at runtime, the owned counterpart of the generated rules.
+#[derive(Clone, Debug)]
+enum Matcher {
+    Offset(usize, Vec<u8>),
+    OffsetRange(usize, usize, Vec<u8>),
+    OffsetCaseInsensitive(usize, Vec<u8>),
+    OffsetRangeCaseInsensitive(usize, usize, Vec<u8>),
+    OffsetMask(usize, Vec<u8>, Vec<u8>),
+    OffsetMaskRange(usize, usize, Vec<u8>, Vec<u8>),
+    Regex(usize, Regex),
+    RegexRange(usize, usize, Regex),
+    UnicodeLe(usize, Vec<u8>),
+    UnicodeLeRange(usize, usize, Vec<u8>),
+    /// The needles of the local name, see `rootxml_local_needles`, and the namespace
+    RootXml(Option<[Vec<u8>; 3]>, Option<String>),
+    And(Vec<Matcher>),
+    Or(Vec<Matcher>),
+    Never,
+}
+
+impl Matcher {
+    fn matches(&self, bytes: &[u8]) -> bool {
+        match self {
+            Matcher::Offset(start, needle) => offset(bytes, *start, needle),
+            Matcher::OffsetRange(start, end, needle) => offset_range(bytes, *start, *end, needle),
+            Matcher::OffsetCaseInsensitive(start, needle) => {
+                offset_case_insensitive(bytes, *start, needle)
+            }
+            Matcher::OffsetRangeCaseInsensitive(start, end, needle) => {
+                offset_range_case_insensitive(bytes, *start, *end, needle)
+            }
+            Matcher::OffsetMask(start, needle, mask) => offset_mask(bytes, *start, needle, mask),
+            Matcher::OffsetMaskRange(start, end, needle, mask) => {
+                offset_mask_range(bytes, *start, *end, needle, mask)
+            }
+            Matcher::Regex(start, needle) => regex(bytes, *start, needle),
+            // Custom types have always matched a range cut short by the end of the
+            // bytes against what there is of it
+            Matcher::RegexRange(start, end, needle) => {
+                regex_range_at(bytes, *start, *end, needle, true).is_some()
+            }
+            Matcher::UnicodeLe(start, needle) => unicode_le(bytes, *start, needle),
+            Matcher::UnicodeLeRange(start, end, needle) => {
+                unicode_le_range_at(bytes, *start, *end, needle, true).is_some()
+            }
+            Matcher::RootXml(Some([a, b, c]), Some(namespace_uri)) => {
+                rootxml(bytes, &[a, b, c], namespace_uri)
+            }
+            Matcher::RootXml(Some([a, b, c]), None) => rootxml_local(bytes, &[a, b, c]),
+            Matcher::RootXml(None, Some(namespace_uri)) => rootxml_namespace(bytes, namespace_uri),
+            Matcher::And(rules) => rules.iter().all(|rule| rule.matches(bytes)),
+            Matcher::Or(rules) => rules.iter().any(|rule| rule.matches(bytes)),
+            Matcher::RootXml(None, None) | Matcher::Never => false,
+        }
+    }
+
+    /// Joins alternatives, like the generator does for the magics of a type
+    fn any(mut rules: Vec<Matcher>) -> Matcher {
+        match rules.len() {
+            0 => Matcher::Never,
+            1 => rules.remove(0),
+            _ => Matcher::Or(rules),
+        }
+    }
+}
+
+/// A type read from a definitions file.
+#[derive(Clone, Debug)]
+struct CustomType {
+    mime: Mime,
+    aliases: Vec<&'static str>,
+    parents: Vec<&'static str>,
+    priority: u32,
+    rule: Matcher,
+}
+
+impl TypeDetector for CustomType {
+    fn mime(&self) -> Mime {
+        self.mime
+    }
+
+    fn check(&self, bytes: &[u8]) -> bool {
+        self.rule.matches(bytes)
+    }
+
+    fn aliases(&self) -> &[&str] {
+        &self.aliases
+    }
+
+    fn parents(&self) -> &[&str] {
+        &self.parents
+    }
+
+    fn priority(&self) -> u32 {
+        self.priority
+    }
+}
+
+/// MIME type definitions in the Tika XML format, loaded at runtime.
+///
+/// Definitions are read from a file like Tika's `custom-mimetypes.xml` and added to
+/// a [`Detector`](crate::Detector) with
+/// [`DetectorBuilder::add_definitions`](crate::DetectorBuilder::add_definitions).
+/// The `magic` and `root-XML` rules of each type are checked, along with its
+/// aliases and `sub-class-of` parents. A type with the name or alias of a built-in
+/// type adds rules to that type.
+///
+/// The names of new types are kept for the life of the program, so they can be
+/// returned as a [`Mime`].
+///
+/// Requires the `custom_mimetypes` feature.
+///
+/// # Examples
+/// ```rust
+/// use tika_magic::{Definitions, Detector, Placement};
+///
+/// let definitions = Definitions::parse(r#"
+///     <mime-info>
+///       <mime-type type="application/x-acme-archive">
+///         <magic priority="60">
+///           <match value="ACME" type="string" offset="0">
+///             <match value="0x0002" type="big16" offset="4"/>
+///           </match>
+///         </magic>
+///       </mime-type>
+///     </mime-info>
+/// "#).unwrap();
+///
+/// let detector = Detector::builder()
+///     .add_definitions(definitions, Placement::BeforeBuiltin)
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(detector.from_u8(b"ACME\x00\x02"), "application/x-acme-archive");
+/// assert_eq!(detector.from_u8(b"ACME\x00\x03"), "application/octet-stream");
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Definitions {
+    types: Vec<CustomType>,
+}
+
+impl Definitions {
+    /// Parses definitions from the text of a Tika MIME types XML file.
+    ///
+    /// Fails with [`Error::InvalidDefinitions`] if the XML is malformed, or if a rule
+    /// has an unknown type, a value that cannot be decoded or a regex that does not
+    /// compile.
+    pub fn parse(xml: &str) -> Result<Self, Error> {
+        Parser::default().parse(xml)
+    }
+
+    /// Reads and parses a Tika MIME types XML file.
+    ///
+    /// Fails with [`Error::Io`] if the file cannot be read, see [`parse`](Self::parse)
+    /// for the other errors.
+    pub fn from_path(path: &Path) -> Result<Self, Error> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// The types that were defined, in file order.
+    pub fn mime_types(&self) -> Vec<Mime> {
+        self.types.iter().map(|t| t.mime).collect()
+    }
+
+    /// The types as detectors, to be registered on a [`Detector`](crate::Detector).
+    pub(crate) fn into_type_detectors(self) -> impl Iterator<Item = Arc<dyn TypeDetector>> {
+        self.types
+            .into_iter()
+            .map(|t| Arc::new(t) as Arc<dyn TypeDetector>)
+    }
+}
+
+fn invalid(reason: impl Into<String>) -> Error {
+    Error::InvalidDefinitions(reason.into())
+}
+
+/// Parses integers the way the generator does, with `0x`, `0o`, `0b` and legacy
+/// octal `0` prefixes
+fn parse_int(input: &str) -> Option<u64> {
+    let input = input.trim();
+    let (digits, radix) = if let Some(hex) = input.strip_prefix("0x").or(input.strip_prefix("0X")) {
+        (hex, 16)
+    } else if let Some(octal) = input.strip_prefix("0o").or(input.strip_prefix("0O")) {
+        (octal, 8)
+    } else if let Some(binary) = input.strip_prefix("0b").or(input.strip_prefix("0B")) {
+        (binary, 2)
+    } else if input.len() > 1 && input.starts_with('0') {
+        (&input[1..], 8)
+    } else {
+        (input, 10)
+    };
+
+    u64::from_str_radix(digits, radix).ok()
+}
+
+/// Decodes a string value, either `0x` hex or text with C style escapes
+fn parse_bytes(input: &str) -> Result<Vec<u8>, Error> {
+    if let Some(hex) = input.strip_prefix("0x").or(input.strip_prefix("0X")) {
+        if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(invalid(format!("invalid hex value \"{input}\"")));
+        }
+        let hex = if hex.len() % 2 != 0 {
+            format!("0{hex}")
+        } else {
+            hex.to_string()
+        };
+
+        return hex
+            .as_bytes()
+            .chunks(2)
+            .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).unwrap_or_default(), 16))
+            .collect::<Result<_, _>>()
+            .map_err(|_| invalid(format!("invalid hex value \"{input}\"")));
+    }
+
+    let mut result = vec![];
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buf = [0; 4];
+            result.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+
+        let Some(next) = chars.next() else {
+            return Err(invalid(format!("dangling escape in \"{input}\"")));
+        };
+        let byte = match next {
+            'x' => {
+                let hex = chars.by_ref().take(2).collect::<String>();
+                u8::from_str_radix(&hex, 16).ok()
+            }
+            '0'..='7' => {
+                let mut octal = next.to_string();
+                while octal.len() < 3 {
+                    match chars.next_if(|c| ('0'..='7').contains(c)) {
+                        Some(c) => octal.push(c),
+                        None => break,
+                    }
+                }
+                u8::from_str_radix(&octal, 8).ok()
+            }
+            'n' => Some(b'\n'),
+            'r' => Some(b'\r'),
+            't' => Some(b'\t'),
+            '\\' | '\'' | '"' | ' ' => Some(next as u8),
+            _ => None,
+        };
+        match byte {
+            Some(byte) => result.push(byte),
+            None => return Err(invalid(format!("invalid escape in \"{input}\""))),
+        }
+    }
+
+    Ok(result)
+}
+
+/// The offset of a match, a single position or an inclusive range
+fn parse_offset(input: Option<&str>) -> Result<(usize, Option<usize>), Error> {
+    let parse = |s: &str| {
+        s.trim()
+            .parse::<usize>()
+            .map_err(|_| invalid(format!("invalid offset \"{}\"", input.unwrap_or_default())))
+    };
+
+    match input {
+        None => Ok((0, None)),
+        Some(input) => match input.split_once(':') {
+            Some((start, end)) => Ok((parse(start)?, Some(parse(end)?))),
+            None => Ok((parse(input)?, None)),
+        },
+    }
+}
+
+fn attribute(element: &BytesStart, name: &str) -> Result<Option<String>, Error> {
+    for attribute in element.attributes() {
+        let attribute = attribute.map_err(|e| invalid(e.to_string()))?;
+        if attribute.key.local_name().as_ref() == name.as_bytes() {
+            let value = attribute
+                .unescape_value()
+                .map_err(|e| invalid(e.to_string()))?;
+            return Ok(Some(value.into_owned()));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Turns a `<match>` element into a rule, without its sub-matches
+fn parse_match(element: &BytesStart) -> Result<Matcher, Error> {
+    let match_type = attribute(element, "type")?.unwrap_or_else(|| "string".to_string());
+    let (start, end) = parse_offset(attribute(element, "offset")?.as_deref())?;
+    let Some(value) = attribute(element, "value")? else {
+        return Ok(Matcher::Never);
+    };
+    let mask = attribute(element, "mask")?;
+
+    let numeric = |width: usize, big_endian: bool, input: &str| -> Result<Vec<u8>, Error> {
+        let number = parse_int(input)
+            .filter(|n| width == 8 || *n < 1 << (width * 8))
+            .ok_or_else(|| invalid(format!("invalid {match_type} value \"{input}\"")))?;
+        let bytes = number.to_le_bytes()[..width].to_vec();
+        Ok(match big_endian {
+            true => bytes.into_iter().rev().collect(),
+            false => bytes,
+        })
+    };
+    let (needle, mask) = match match_type.as_str() {
+        "string" | "stringignorecase" | "unicodeLE" => {
+            let mask = mask.as_deref().map(parse_bytes).transpose()?;
+            (parse_bytes(&value)?, mask)
+        }
+        "regex" => {
+            let regex = Regex::new(&value)
+                .map_err(|e| invalid(format!("invalid regex \"{value}\": {e}")))?;
+            return Ok(match end {
+                Some(end) => Matcher::RegexRange(start, end, regex),
+                None => Matcher::Regex(start, regex),
+            });
+        }
+        numeric_type => {
+            let (width, big_endian) = match numeric_type {
+                "byte" => (1, false),
+                "little16" | "host16" => (2, false),
+                "big16" => (2, true),
+                "little32" | "host32" => (4, false),
+                "big32" => (4, true),
+                _ => return Err(invalid(format!("unsupported match type \"{match_type}\""))),
+            };
+            let mask = mask
+                .as_deref()
+                .map(|mask| numeric(width, big_endian, mask))
+                .transpose()?;
+            (numeric(width, big_endian, &value)?, mask)
+        }
+    };
+
+    Ok(match (match_type.as_str(), end, mask) {
+        ("stringignorecase", _, Some(_)) | ("unicodeLE", _, Some(_)) => {
+            return Err(invalid(format!("masks are not supported on {match_type}")))
+        }
+        ("stringignorecase", None, None) => Matcher::OffsetCaseInsensitive(start, needle),
+        ("stringignorecase", Some(end), None) => {
+            Matcher::OffsetRangeCaseInsensitive(start, end, needle)
+        }
+        ("unicodeLE", None, None) => Matcher::UnicodeLe(start, needle),
+        ("unicodeLE", Some(end), None) => Matcher::UnicodeLeRange(start, end, needle),
+        (_, None, None) => Matcher::Offset(start, needle),
+        (_, Some(end), None) => Matcher::OffsetRange(start, end, needle),
+        (_, None, Some(mask)) => Matcher::OffsetMask(start, masked(needle, &mask), mask),
+        (_, Some(end), Some(mask)) => {
+            Matcher::OffsetMaskRange(start, end, masked(needle, &mask), mask)
+        }
+    })
+}
+
+/// Masks a needle up front, like the generator does
+fn masked(mut needle: Vec<u8>, mask: &[u8]) -> Vec<u8> {
+    needle.iter_mut().zip(mask).for_each(|(n, m)| *n &= m);
+    needle
+}
+
+/// A type whose closing tag has not been read yet
+#[derive(Default)]
+struct PendingType {
+    name: String,
+    aliases: Vec<String>,
+    parents: Vec<String>,
+    priority: u32,
+    rules: Vec<Matcher>,
+}
+
+/// Walks the XML events, keeping the elements that are still open
+#[derive(Default)]
+struct Parser {
+    types: Vec<CustomType>,
+    pending: Option<PendingType>,
+    magic: Option<(u32, Vec<Matcher>)>,
+    matches: Vec<(Matcher, Vec<Matcher>)>,
+}
+
+impl Parser {
+    fn parse(mut self, xml: &str) -> Result<Definitions, Error> {
+        let mut reader = Reader::from_str(xml);
+        loop {
+            let event = reader.read_event().map_err(|e| {
+                invalid(format!(
+                    "malformed XML at byte {}: {e}",
+                    reader.error_position()
+                ))
+            })?;
+            match event {
+                Event::Start(element) => self.open(&element)?,
+                Event::Empty(element) => {
+                    self.open(&element)?;
+                    self.close(element.local_name().as_ref())?;
+                }
+                Event::End(element) => self.close(element.local_name().as_ref())?,
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+
+        Ok(Definitions { types: self.types })
+    }
+
+    fn open(&mut self, element: &BytesStart) -> Result<(), Error> {
+        let name = element.local_name();
+        if name.as_ref() == b"mime-type" {
+            let mimetype = attribute(element, "type")?
+                .ok_or_else(|| invalid("mime-type without a type attribute"))?;
+            MediaType::parse(&mimetype)
+                .map_err(|e| invalid(format!("invalid mime-type \"{mimetype}\": {e}")))?;
+            self.pending = Some(PendingType {
+                name: mimetype,
+                ..Default::default()
+            });
+            return Ok(());
+        }
+
+        let Some(pending) = self.pending.as_mut() else {
+            return Ok(());
+        };
+        match name.as_ref() {
+            b"alias" => pending.aliases.extend(attribute(element, "type")?),
+            b"sub-class-of" => pending.parents.extend(attribute(element, "type")?),
+            b"root-XML" => pending.rules.push(Matcher::RootXml(
+                attribute(element, "localName")?
+                    .map(|local_name| rootxml_local_needles(&local_name)),
+                attribute(element, "namespaceURI")?,
+            )),
+            b"magic" => {
+                let priority = match attribute(element, "priority")? {
+                    Some(priority) => priority
+                        .trim()
+                        .parse()
+                        .map_err(|_| invalid(format!("invalid magic priority \"{priority}\"")))?,
+                    None => 0,
+                };
+                self.magic = Some((priority, vec![]));
+            }
+            b"match" if self.magic.is_some() => {
+                self.matches.push((parse_match(element)?, vec![]));
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn close(&mut self, name: &[u8]) -> Result<(), Error> {
+        match name {
+            b"match" => {
+                let Some((rule, sub_matches)) = self.matches.pop() else {
+                    return Ok(());
+                };
+                let rule = match sub_matches.len() {
+                    0 => rule,
+                    _ => Matcher::And(vec![rule, Matcher::any(sub_matches)]),
+                };
+                match (self.matches.last_mut(), self.magic.as_mut()) {
+                    (Some((_, parent)), _) => parent.push(rule),
+                    (None, Some((_, rules))) => rules.push(rule),
+                    (None, None) => {}
+                }
+            }
+            b"magic" => {
+                if let (Some((priority, rules)), Some(pending)) =
+                    (self.magic.take(), self.pending.as_mut())
+                {
+                    pending.priority = pending.priority.max(priority);
+                    pending.rules.extend(rules);
+                }
+            }
+            b"mime-type" => {
+                if let Some(pending) = self.pending.take() {
+                    self.types.push(CustomType {
+                        mime: intern_type(&pending.name),
+                        aliases: pending.aliases.iter().map(|a| intern(a)).collect(),
+                        parents: pending.parents.iter().map(|p| intern_type(p)).collect(),
+                        priority: pending.priority,
+                        rule: Matcher::any(pending.rules),
+                    });
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "all_types"))]
+mod tests {
+    use super::*;
+    use crate::{Detector, DetectorBuilder, Placement};
+
+    fn detector(xml: &str, placement: Placement) -> Detector {
+        DetectorBuilder::new()
+            .add_definitions(Definitions::parse(xml).unwrap(), placement)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parse_values() {
+        assert_eq!(parse_bytes("PK\\003\\004").unwrap(), b"PK\x03\x04");
+        assert_eq!(parse_bytes("\\x89PNG\\r\\n").unwrap(), b"\x89PNG\r\n");
+        assert_eq!(parse_bytes("0xCAFEBABE").unwrap(), b"\xca\xfe\xba\xbe");
+        assert_eq!(parse_bytes("0xfff").unwrap(), b"\x0f\xff");
+        assert!(parse_bytes("bad\\q").is_err());
+        assert_eq!(parse_int("0x1F"), Some(31));
+        assert_eq!(parse_int("017"), Some(15));
+        assert_eq!(parse_int("42"), Some(42));
+    }
+
+    #[test]
+    fn test_match_types() {
+        let definitions = Definitions::parse(
+            r#"<mime-info>
+              <mime-type type="application/x-little">
+                <magic><match value="0x0102" type="little16" offset="2"/></magic>
+              </mime-type>
+              <mime-type type="application/x-big">
+                <magic><match value="0xcafebabe" type="big32" offset="0"/></magic>
+              </mime-type>
+              <mime-type type="application/x-masked">
+                <magic><match value="0x4000" mask="0xF000" type="string" offset="0:4"/></magic>
+              </mime-type>
+              <mime-type type="application/x-regex">
+                <magic><match value="v[0-9]+\.[0-9]+" type="regex" offset="0:16"/></magic>
+              </mime-type>
+              <mime-type type="application/x-wide">
+                <magic><match value="Wide" type="unicodeLE" offset="0"/></magic>
+              </mime-type>
+              <mime-type type="application/x-shouting">
+                <magic><match value="hello" type="stringignorecase" offset="0"/></magic>
+              </mime-type>
+            </mime-info>"#,
+        )
+        .unwrap();
+        let rule = |mime: &str| {
+            let t = definitions.types.iter().find(|t| t.mime == mime).unwrap();
+            t.rule.clone()
+        };
+
+        assert!(rule("application/x-little").matches(b"xx\x02\x01"));
+        assert!(!rule("application/x-little").matches(b"xx\x01\x02"));
+        assert!(rule("application/x-big").matches(b"\xca\xfe\xba\xbe"));
+        assert!(rule("application/x-masked").matches(b"..\x4a\x00"));
+        assert!(rule("application/x-regex").matches(b"format v12.3"));
+        assert!(matches!(
+            rule("application/x-wide"),
+            Matcher::UnicodeLe(0, needle) if needle == b"Wide"
+        ));
+        assert!(rule("application/x-shouting").matches(b"HELLO"));
+    }
+
+    #[test]
+    fn test_invalid_definitions() {
+        let cases = [
+            "<mime-info><mime-type type=\"a/b\"><magic><match value=\"x\" type=\"host64\"/></magic></mime-type></mime-info>",
+            "<mime-info><mime-type type=\"a/b\"><magic><match value=\"(\" type=\"regex\" offset=\"0\"/></magic></mime-type></mime-info>",
+            "<mime-info><mime-type type=\"a/b\"><magic><match value=\"0x10000\" type=\"big16\"/></magic></mime-type></mime-info>",
+            "<mime-info><mime-type type=\"a/b\"><magic><match value=\"0xé0\" type=\"string\" offset=\"0\"/></magic></mime-type></mime-info>",
+            "<mime-info><mime-type type=\"a/b\"><magic><match value=\"0x+f\" type=\"string\" offset=\"0\"/></magic></mime-type></mime-info>",
+            "<mime-info><mime-type type=\"not a type\"></mime-type></mime-info>",
+            "<mime-info><mime-type type=\"a/b\"></mime-info>",
+        ];
+        for xml in cases {
+            assert!(
+                matches!(Definitions::parse(xml), Err(Error::InvalidDefinitions(_))),
+                "{xml}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_placement() {
+        // Claims every PNG, but with a lower priority than the built-in PNG magic
+        let xml = r#"<mime-info>
+          <mime-type type="image/x-acme-png">
+            <magic priority="10"><match value="\x89PNG" type="string" offset="0"/></magic>
+          </mime-type>
+        </mime-info>"#;
+        let data = include_bytes!("../tests/inputs/image/png/png.png");
+
+        let before = detector(xml, Placement::BeforeBuiltin);
+        assert_eq!(before.from_u8(data), "image/x-acme-png");
+        assert_eq!(before.custom_types(), ["image/x-acme-png"]);
+
+        let by_priority = detector(xml, Placement::ByPriority);
+        assert_eq!(by_priority.from_u8(data), "image/png");
+        assert!(by_priority.match_u8("image/x-acme-png", data));
+
+        let after = detector(xml, Placement::AfterBuiltin);
+        assert_eq!(after.from_u8(data), "image/png");
+        assert_eq!(after.from_u8(b"\x89PNG but not really"), "image/x-acme-png");
+    }
+
+    #[test]
+    fn test_sub_class_of_builtin() {
+        let xml = r#"<mime-info>
+          <mime-type type="application/x-acme+xml">
+            <alias type="text/x-acme"/>
+            <sub-class-of type="text/xml"/>
+            <root-XML localName="acme" namespaceURI="urn:acme"/>
+          </mime-type>
+        </mime-info>"#;
+        let data = b"<?xml version=\"1.0\"?>\n<acme xmlns=\"urn:acme\"></acme>";
+
+        // Found as a child of application/xml, even when checked after the built-ins
+        let detector = detector(xml, Placement::AfterBuiltin);
+        assert_eq!(detector.from_u8(data), "application/x-acme+xml");
+        assert!(detector.match_u8("Text/X-Acme", data));
+        assert!(detector
+            .try_match_filepath("text/x-acme", Path::new("./tests/inputs/image/png/png.png"))
+            .is_ok());
+        assert_eq!(crate::from_u8(data), "application/xml");
+    }
+
+    #[test]
+    fn test_extends_builtin() {
+        // New magic for a built-in type, given by its alias
+        let xml = r#"<mime-info>
+          <mime-type type="text/xml">
+            <magic priority="50"><match value="XMLISH" type="string" offset="0"/></magic>
+          </mime-type>
+        </mime-info>"#;
+        let definitions = Definitions::parse(xml).unwrap();
+        assert_eq!(definitions.mime_types(), ["application/xml"]);
+
+        let detector = detector(xml, Placement::ByPriority);
+        assert_eq!(detector.from_u8(b"XMLISH"), "application/xml");
+    }
+}
@@ -0,0 +1,49 @@
+use crate::{registry, to_media_type, MediaType, Mime};
+use std::fmt;
+
+/// The result of a successful `try_*` detection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Detection {
+    #[cfg_attr(
+        feature = "serde",
+        serde(deserialize_with = "crate::serialization::mime")
+    )]
+    mime: Mime,
+    bytes_read: usize,
+}
+
+impl Detection {
+    pub(crate) fn new(mime: Mime, bytes_read: usize) -> Self {
+        Detection { mime, bytes_read }
+    }
+
+    /// The detected type.
+    pub fn mime(&self) -> Mime {
+        self.mime
+    }
+
+    /// The detected type as a [`MediaType`].
+    pub fn media_type(&self) -> MediaType {
+        to_media_type(self.mime)
+    }
+
+    /// The database entry of the detected type.
+    ///
+    /// Returns None for the few types only the container handlers report, such as
+    /// `application/vnd.openxmlformats-officedocument`.
+    pub fn info(&self) -> Option<registry::MimeTypeInfo> {
+        registry::lookup(self.mime)
+    }
+
+    /// How many bytes of the input were read for the detection.
+    pub fn bytes_read(&self) -> usize {
+        self.bytes_read
+    }
+}
+
+impl fmt::Display for Detection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.mime)
+    }
+}
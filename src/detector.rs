@@ -0,0 +1,1016 @@
+#[cfg(feature = "custom_mimetypes")]
+use crate::definitions::Definitions;
+use crate::explain::Found;
+use crate::magic::{MimeTypeChecker, RangeHits, MIME_MAP, MIME_TYPES, PRIORITY_MIME_TYPES};
+use crate::pipeline::{self, specialize, DEFAULT_PIPELINE};
+use crate::type_detector::CustomTypes;
+use crate::{
+    canonicalize, check_recursive, from_filename, is_a, maybe_open_ole, maybe_open_ole_stream,
+    maybe_open_zip, maybe_open_zip_stream, registry, to_media_type, Detection, Error, MediaType,
+    Mime, PipelineStage, Placement, Stage, TypeDetector, HEAD_SIZE,
+};
+use std::borrow::Cow;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Opens the ZIP or OLE container the bytes are, if that kind is to be opened.
+///
+/// Takes the settings rather than a [`Detector`], so it can run on another thread
+/// without the detector.
+pub(crate) fn open_containers(bytes: &[u8], open_zips: bool, open_ole: bool) -> Option<Mime> {
+    if open_zips {
+        if let Some(mime) = maybe_open_zip(bytes) {
+            return Some(mime);
+        }
+    }
+
+    if open_ole {
+        if let Some(mime) = maybe_open_ole(bytes) {
+            return Some(mime);
+        }
+    }
+
+    None
+}
+
+/// The detector behind the free functions of this crate.
+pub(crate) static DEFAULT_DETECTOR: Detector = Detector::DEFAULT;
+
+/// A MIME type detector with its own settings.
+///
+/// The free functions of this crate use a detector with the default settings. Build
+/// a `Detector` with [`DetectorBuilder`] to change how much of a file is read,
+/// whether ZIP and OLE containers are opened, which types are checked first, which
+/// [stages](PipelineStage) run in which order and what is returned when nothing
+/// matches.
+///
+/// A detector is cheap to share between threads.
+///
+/// # Examples
+/// ```rust
+/// use tika_magic::Detector;
+///
+/// // Only read the first 4 KiB and never open containers
+/// let detector = Detector::builder()
+///     .max_bytes(4096)
+///     .open_zips(false)
+///     .open_ole(false)
+///     .fallback("text/plain")
+///     .build()
+///     .unwrap();
+///
+/// // Load an Excel 5 file
+/// let input: &[u8] = include_bytes!("../tests/inputs/application/vnd.ms-excel/testEXCEL_5.xls");
+///
+/// // Without opening the compound document, it is only recognized as OLE storage
+/// assert_eq!(detector.from_u8(input), "application/x-ole-storage");
+/// assert_eq!(detector.from_u8(&[0xfa, 0xce, 0xb0, 0x0c]), "text/plain");
+/// ```
+#[derive(Clone)]
+pub struct Detector {
+    max_bytes: usize,
+    open_zips: bool,
+    open_ole: bool,
+    priority: Cow<'static, [&'static dyn MimeTypeChecker]>,
+    pipeline: Cow<'static, [PipelineStage]>,
+    fallback: Mime,
+    custom: CustomTypes,
+}
+
+impl Detector {
+    const DEFAULT: Detector = Detector {
+        max_bytes: HEAD_SIZE,
+        open_zips: true,
+        open_ole: true,
+        priority: Cow::Borrowed(PRIORITY_MIME_TYPES),
+        pipeline: Cow::Borrowed(DEFAULT_PIPELINE),
+        fallback: "application/octet-stream",
+        custom: CustomTypes::EMPTY,
+    };
+
+    /// Creates a detector with the default settings, the same ones the free
+    /// functions use.
+    pub fn new() -> Self {
+        Self::DEFAULT
+    }
+
+    /// Starts building a detector from the default settings.
+    pub fn builder() -> DetectorBuilder {
+        DetectorBuilder::new()
+    }
+
+    /// How many bytes are read from the start of a file or stream.
+    pub fn max_bytes(&self) -> usize {
+        self.max_bytes
+    }
+
+    /// Whether ZIP archives are opened to tell their formats apart.
+    ///
+    /// Always false when the crate is built without the `open_zips` feature.
+    pub fn open_zips(&self) -> bool {
+        self.open_zips && cfg!(feature = "open_zips")
+    }
+
+    /// Whether OLE compound documents are opened to tell their formats apart.
+    ///
+    /// Always false when the crate is built without the `open_ole` feature.
+    pub fn open_ole(&self) -> bool {
+        self.open_ole && cfg!(feature = "open_ole")
+    }
+
+    /// The types checked before the full list, in order.
+    pub fn priority_types(&self) -> Vec<Mime> {
+        self.priority.iter().map(|m| m.get_mime()).collect()
+    }
+
+    /// The stages of the detection, in the order they run.
+    pub fn pipeline(&self) -> &[PipelineStage] {
+        &self.pipeline
+    }
+
+    /// The type returned when nothing matches.
+    pub fn fallback(&self) -> Mime {
+        self.fallback
+    }
+
+    /// The types registered with [`DetectorBuilder::add_type_detector`] or loaded
+    /// with [`DetectorBuilder::add_definitions`].
+    pub fn custom_types(&self) -> Vec<Mime> {
+        self.custom.mime_types()
+    }
+
+    pub(crate) fn custom(&self) -> &CustomTypes {
+        &self.custom
+    }
+
+    /// Whether a type is built in or registered
+    fn is_known(&self, mimetype: &str) -> bool {
+        canonicalize(mimetype).is_some() || self.custom.matches(mimetype, &[]).is_some()
+    }
+
+    /// Reads up to `max_bytes`, retrying short reads until the head is full or the
+    /// input ends.
+    pub(crate) fn read_head<R: Read>(&self, reader: R) -> io::Result<Vec<u8>> {
+        crate::read_head(reader, self.max_bytes)
+    }
+
+    /// Whether a head read by `read_head` is the whole input, which ended before
+    /// `max_bytes`.
+    pub(crate) fn is_whole(&self, head: &[u8]) -> bool {
+        head.len() < self.max_bytes
+    }
+
+    /// Reads the head of a file for the `try_*` methods.
+    fn try_read_head(&self, file: &File) -> Result<Vec<u8>, Error> {
+        let head = self.read_head(file)?;
+        if head.is_empty() {
+            return Err(Error::Empty);
+        }
+
+        Ok(head)
+    }
+
+    pub(crate) fn handle_special_files(&self, bytes: &[u8]) -> Option<Mime> {
+        open_containers(bytes, self.open_zips, self.open_ole)
+    }
+
+    /// Like `handle_special_files`, but gives the handlers the whole stream starting
+    /// at `start` rather than just its head.
+    fn handle_special_streams<R: Read + Seek>(
+        &self,
+        reader: &mut R,
+        start: u64,
+        head: &[u8],
+    ) -> Option<Mime> {
+        if self.open_zips {
+            reader.seek(SeekFrom::Start(start)).ok()?;
+            if let Some(mime) = maybe_open_zip_stream(head, &mut *reader) {
+                return Some(mime);
+            }
+        }
+
+        if self.open_ole {
+            reader.seek(SeekFrom::Start(start)).ok()?;
+            if let Some(mime) = maybe_open_ole_stream(head, &mut *reader) {
+                return Some(mime);
+            }
+        }
+
+        None
+    }
+
+    /// Whether a container stage could open an input starting with the head.
+    #[cfg(feature = "tokio")]
+    pub(crate) fn may_open_container(&self, head: &[u8]) -> bool {
+        let could_start = |magic: &[u8]| head.starts_with(magic) || magic.starts_with(head);
+        self.pipeline.contains(&PipelineStage::Container)
+            && ((self.open_zips() && could_start(crate::magic::ZipSpecialHandler::MAGIC))
+                || (self.open_ole() && could_start(crate::magic::OleSpecialHandler::MAGIC)))
+    }
+
+    /// Whether the rest of the input can no longer change the type detected from
+    /// the head, so it need not be read.
+    ///
+    /// The magic settles once, in the order it is checked in, a type is sure to
+    /// match and every type before it is sure not to. A container is opened from
+    /// the whole head and registered types may look anywhere, so they don't settle
+    /// before the head is full.
+    #[cfg(feature = "tokio")]
+    pub(crate) fn is_settled(&self, head: &[u8]) -> bool {
+        self.custom.is_empty()
+            && self.pipeline.iter().all(|stage| match stage {
+                PipelineStage::Container => !self.may_open_container(head),
+                PipelineStage::Magic => {
+                    let hits = RangeHits::new(head);
+                    self.priority
+                        .iter()
+                        .chain(MIME_TYPES)
+                        .map(|checker| crate::settled_recursive(*checker, head, &hits))
+                        .find(|settled| *settled != Some(false))
+                        .map_or(true, |settled| settled.is_some())
+                }
+                PipelineStage::XmlRoot => pipeline::xml_root_is_settled(head),
+                PipelineStage::Text => pipeline::text_is_settled(head),
+                PipelineStage::Name => true,
+            })
+    }
+
+    /// Checks the magic of every type, priority types first.
+    ///
+    /// `whole` tells whether the bytes are the whole input, see [`RangeHits::whole`].
+    fn check_magic(&self, bytes: &[u8], whole: bool) -> Option<Found> {
+        let hits = RangeHits::new(bytes).whole(whole);
+        self.custom
+            .check_before(bytes)
+            .map(|mime| Found::new(mime, Stage::Custom))
+            .or_else(|| {
+                self.priority.iter().find_map(|checker| {
+                    check_recursive(*checker, bytes, &hits)
+                        .map(|mime| Found::walked(mime, Stage::PriorityList, *checker))
+                })
+            })
+            .or_else(|| self.custom.check_main(bytes, &hits))
+            .or_else(|| {
+                self.custom
+                    .check_after(bytes)
+                    .map(|mime| Found::new(mime, Stage::Custom))
+            })
+    }
+
+    /// Runs the pipeline on a head, None if no stage found anything.
+    ///
+    /// `container` runs the container stage, which may look past the head. Walks
+    /// down from the detected type to the registered types that are a subtype of
+    /// it, whichever stage found it.
+    pub(crate) fn run<F>(
+        &self,
+        bytes: &[u8],
+        whole: bool,
+        name: Option<&str>,
+        container: F,
+    ) -> Option<Mime>
+    where
+        F: FnMut() -> Option<Mime>,
+    {
+        self.run_recorded(bytes, whole, name, container, &mut |_| {})
+    }
+
+    /// Like `run`, telling `record` what each step that made the answer more
+    /// specific found, in order.
+    pub(crate) fn run_recorded<F>(
+        &self,
+        bytes: &[u8],
+        whole: bool,
+        name: Option<&str>,
+        mut container: F,
+        record: &mut dyn FnMut(Found),
+    ) -> Option<Mime>
+    where
+        F: FnMut() -> Option<Mime>,
+    {
+        let mut mime = None;
+        for stage in self.pipeline.iter() {
+            let found = match stage {
+                PipelineStage::Container => {
+                    container().map(|mime| Found::new(mime, Stage::container(bytes)))
+                }
+                PipelineStage::Magic => self.check_magic(bytes, whole),
+                PipelineStage::XmlRoot => {
+                    pipeline::xml_root(bytes, mime).map(|mime| Found::new(mime, Stage::XmlRoot))
+                }
+                PipelineStage::Text => {
+                    pipeline::text(bytes).map(|mime| Found::new(mime, Stage::Text))
+                }
+                PipelineStage::Name => name
+                    .and_then(from_filename)
+                    .map(|mime| Found::new(mime, Stage::Name)),
+            };
+
+            let specialized = specialize(mime, found.map(|found| found.mime));
+            if let Some(found) = found.filter(|_| specialized != mime) {
+                record(found);
+            }
+            mime = specialized;
+        }
+
+        let mime = mime?;
+        let refined = self.custom.refine(mime, bytes);
+        if refined != mime {
+            record(Found::new(refined, Stage::Custom));
+        }
+
+        Some(refined)
+    }
+
+    /// Detects the type of a head, None if nothing matched.
+    pub(crate) fn detect(&self, bytes: &[u8], whole: bool, name: Option<&str>) -> Option<Mime> {
+        self.run(bytes, whole, name, || self.handle_special_files(bytes))
+    }
+
+    /// Checks if the given bytestream matches the given MIME type.
+    ///
+    /// See [`match_u8`](crate::match_u8).
+    pub fn match_u8(&self, mimetype: &str, bytes: &[u8]) -> bool {
+        self.match_head(mimetype, bytes, false)
+    }
+
+    /// Like `match_u8`, `whole` telling whether the bytes are the whole input.
+    fn match_head(&self, mimetype: &str, bytes: &[u8], whole: bool) -> bool {
+        if self.custom.matches(mimetype, bytes) == Some(true) {
+            return true;
+        }
+
+        // The types of the exact name, or else of the canonical name it resolves to
+        // ignoring case and parameters
+        let mm = match MIME_MAP.get(mimetype) {
+            Some(mm) => *mm,
+            None => registry::resolve(mimetype)
+                .and_then(|checker| MIME_MAP.get(checker.get_mime()))
+                .copied()
+                .unwrap_or_default(),
+        };
+
+        // A recognized ZIP or OLE container is only a match for the type it was recognized as
+        let container = self.pipeline.contains(&PipelineStage::Container);
+        if let Some(mime) = container
+            .then(|| self.handle_special_files(bytes))
+            .flatten()
+        {
+            return mime == mimetype || mm.iter().any(|m| m.get_mime() == mime);
+        }
+
+        let hits = RangeHits::none().whole(whole);
+        mm.iter().any(|m| m.check_with(bytes, &hits))
+    }
+
+    /// Checks if the given bytestream matches the given MIME type or one of its
+    /// subtypes.
+    ///
+    /// See [`match_u8_or_subtype`](crate::match_u8_or_subtype).
+    pub fn match_u8_or_subtype(&self, mimetype: &str, bytes: &[u8]) -> bool {
+        self.match_head_or_subtype(mimetype, bytes, false)
+    }
+
+    /// Like `match_u8_or_subtype`, `whole` telling whether the bytes are the whole
+    /// input.
+    fn match_head_or_subtype(&self, mimetype: &str, bytes: &[u8], whole: bool) -> bool {
+        // The fallback is no detection, so it doesn't count as a match
+        self.match_head(mimetype, bytes, whole)
+            || self
+                .detect(bytes, whole, None)
+                .is_some_and(|mime| is_a(mime, mimetype))
+    }
+
+    /// Gets the MIME from a byte stream.
+    ///
+    /// See [`from_u8`](crate::from_u8).
+    pub fn from_u8(&self, bytes: &[u8]) -> Mime {
+        self.detect(bytes, false, None).unwrap_or(self.fallback)
+    }
+
+    /// Gets the MIME types that match a byte stream.
+    ///
+    /// See [`from_u8_exhaustive`](crate::from_u8_exhaustive).
+    pub fn from_u8_exhaustive(&self, bytes: &[u8]) -> Vec<Mime> {
+        self.exhaustive(bytes, false)
+    }
+
+    /// Like `from_u8_exhaustive`, `whole` telling whether the bytes are the whole
+    /// input.
+    fn exhaustive(&self, bytes: &[u8], whole: bool) -> Vec<Mime> {
+        let hits = RangeHits::new(bytes).whole(whole);
+        MIME_TYPES
+            .iter()
+            .filter(|m| m.check_with(bytes, &hits))
+            .map(|m| m.get_mime())
+            .collect()
+    }
+
+    /// Gets the MIME type of a byte stream as a [`MediaType`].
+    ///
+    /// See [`from_u8_media_type`](crate::from_u8_media_type).
+    pub fn from_u8_media_type(&self, bytes: &[u8]) -> MediaType {
+        to_media_type(self.from_u8(bytes))
+    }
+
+    /// Gets the MIME type from a byte stream and the name it was stored under.
+    ///
+    /// See [`from_u8_with_name`](crate::from_u8_with_name).
+    pub fn from_u8_with_name(&self, bytes: &[u8], name: &str) -> Mime {
+        self.detect(bytes, false, Some(name))
+            .unwrap_or(self.fallback)
+    }
+
+    /// Check if the given file matches the given MIME type.
+    ///
+    /// See [`match_file`](crate::match_file).
+    pub fn match_file(&self, mimetype: &str, file: &File) -> bool {
+        match self.read_head(file) {
+            Ok(head) if !head.is_empty() => self.match_head(mimetype, &head, self.is_whole(&head)),
+            _ => false,
+        }
+    }
+
+    /// Check if the given file matches the given MIME type or one of its subtypes.
+    ///
+    /// See [`match_file_or_subtype`](crate::match_file_or_subtype).
+    pub fn match_file_or_subtype(&self, mimetype: &str, file: &File) -> bool {
+        match self.read_head(file) {
+            Ok(head) if !head.is_empty() => {
+                self.match_head_or_subtype(mimetype, &head, self.is_whole(&head))
+            }
+            _ => false,
+        }
+    }
+
+    /// Check if the file at the given path matches the given MIME type.
+    ///
+    /// See [`match_filepath`](crate::match_filepath).
+    pub fn match_filepath(&self, mimetype: &str, path: &Path) -> bool {
+        match File::open(path) {
+            Ok(file) => self.match_file(mimetype, &file),
+            Err(_) => false,
+        }
+    }
+
+    /// Check if the file at the given path matches the given MIME type or one of its
+    /// subtypes.
+    ///
+    /// See [`match_filepath_or_subtype`](crate::match_filepath_or_subtype).
+    pub fn match_filepath_or_subtype(&self, mimetype: &str, path: &Path) -> bool {
+        match File::open(path) {
+            Ok(file) => self.match_file_or_subtype(mimetype, &file),
+            Err(_) => false,
+        }
+    }
+
+    /// Gets the MIME type of the data read from a reader.
+    ///
+    /// See [`from_reader`](crate::from_reader).
+    pub fn from_reader<R: Read>(&self, reader: R) -> Option<Mime> {
+        match self.read_head(reader) {
+            Ok(head) if !head.is_empty() => Some(self.detect_head(&head)),
+            _ => None,
+        }
+    }
+
+    /// Detects the type of a head read by `read_head`.
+    pub(crate) fn detect_head(&self, head: &[u8]) -> Mime {
+        self.detect(head, self.is_whole(head), None)
+            .unwrap_or(self.fallback)
+    }
+
+    /// Like `detect_head`, with what the container stage finds found beforehand and
+    /// `whole` telling whether the head is the whole input.
+    #[cfg(feature = "tokio")]
+    pub(crate) fn detect_opened(&self, head: &[u8], whole: bool, container: Option<Mime>) -> Mime {
+        self.run(head, whole, None, || container)
+            .unwrap_or(self.fallback)
+    }
+
+    /// Gets the MIME type of the data in a seekable reader.
+    ///
+    /// See [`from_seekable`](crate::from_seekable).
+    pub fn from_seekable<R: Read + Seek>(&self, mut reader: R) -> Option<Mime> {
+        let start = reader.stream_position().ok()?;
+        let head = self.read_head(&mut reader).ok()?;
+        if head.is_empty() {
+            return None;
+        }
+
+        let mime = self.run(&head, self.is_whole(&head), None, || {
+            self.handle_special_streams(&mut reader, start, &head)
+        });
+        reader.seek(SeekFrom::Start(start)).ok()?;
+
+        Some(mime.unwrap_or(self.fallback))
+    }
+
+    /// Gets the MIME type for a file.
+    ///
+    /// See [`from_file`](crate::from_file).
+    pub fn from_file(&self, file: &File) -> Option<Mime> {
+        self.from_reader(file)
+    }
+
+    /// Gets all the MIME types that match a file.
+    ///
+    /// See [`from_file_exhaustive`](crate::from_file_exhaustive).
+    pub fn from_file_exhaustive(&self, file: &File) -> Option<Vec<Mime>> {
+        match self.read_head(file) {
+            Ok(head) if !head.is_empty() => Some(self.exhaustive(&head, self.is_whole(&head))),
+            _ => None,
+        }
+    }
+
+    /// Gets the MIME type for a path.
+    ///
+    /// See [`from_filepath`](crate::from_filepath).
+    pub fn from_filepath(&self, path: &Path) -> Option<Mime> {
+        match File::open(path) {
+            Ok(file) => self.from_file(&file),
+            Err(_) => None,
+        }
+    }
+
+    /// Gets all the MIME types that match for a path.
+    ///
+    /// See [`from_filepath_exhaustive`](crate::from_filepath_exhaustive).
+    pub fn from_filepath_exhaustive(&self, path: &Path) -> Option<Vec<Mime>> {
+        match File::open(path) {
+            Ok(file) => self.from_file_exhaustive(&file),
+            Err(_) => None,
+        }
+    }
+
+    /// Gets the MIME type for a path as a [`MediaType`].
+    ///
+    /// See [`from_filepath_media_type`](crate::from_filepath_media_type).
+    pub fn from_filepath_media_type(&self, path: &Path) -> Option<MediaType> {
+        self.from_filepath(path).map(to_media_type)
+    }
+
+    /// Gets the MIME type for a path, using both its contents and its file name.
+    ///
+    /// See [`from_filepath_with_name`](crate::from_filepath_with_name).
+    pub fn from_filepath_with_name(&self, path: &Path) -> Option<Mime> {
+        let head = self.read_head(File::open(path).ok()?).ok()?;
+        if head.is_empty() {
+            return None;
+        }
+
+        let name = path.file_name().and_then(|name| name.to_str());
+        Some(
+            self.detect(&head, self.is_whole(&head), name)
+                .unwrap_or(self.fallback),
+        )
+    }
+
+    /// Gets the MIME type for a file, telling apart why detection failed.
+    ///
+    /// See [`try_from_file`](crate::try_from_file). Nothing matching is an
+    /// [`Error::Unidentified`], whatever the fallback type is.
+    pub fn try_from_file(&self, file: &File) -> Result<Detection, Error> {
+        let head = self.try_read_head(file)?;
+
+        match self.detect(&head, self.is_whole(&head), None) {
+            Some(mime) => Ok(Detection::new(mime, head.len())),
+            None => Err(Error::Unidentified),
+        }
+    }
+
+    /// Gets the MIME type for a path, telling apart why detection failed.
+    ///
+    /// See [`try_from_filepath`](crate::try_from_filepath).
+    pub fn try_from_filepath(&self, path: &Path) -> Result<Detection, Error> {
+        self.try_from_file(&File::open(path)?)
+    }
+
+    /// Checks if a file matches the given MIME type, telling apart why the check
+    /// could not be done.
+    ///
+    /// See [`try_match_file`](crate::try_match_file).
+    pub fn try_match_file(&self, mimetype: &str, file: &File) -> Result<bool, Error> {
+        if !self.is_known(mimetype) {
+            return Err(Error::UnknownMimeType(mimetype.to_string()));
+        }
+
+        let head = self.try_read_head(file)?;
+
+        Ok(self.match_head(mimetype, &head, self.is_whole(&head)))
+    }
+
+    /// Checks if the file at the given path matches the given MIME type, telling
+    /// apart why the check could not be done.
+    ///
+    /// See [`try_match_filepath`](crate::try_match_filepath).
+    pub fn try_match_filepath(&self, mimetype: &str, path: &Path) -> Result<bool, Error> {
+        if !self.is_known(mimetype) {
+            return Err(Error::UnknownMimeType(mimetype.to_string()));
+        }
+
+        self.try_match_file(mimetype, &File::open(path)?)
+    }
+}
+
+impl Default for Detector {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl fmt::Debug for Detector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Detector")
+            .field("max_bytes", &self.max_bytes)
+            .field("open_zips", &self.open_zips())
+            .field("open_ole", &self.open_ole())
+            .field("priority_types", &self.priority_types())
+            .field("pipeline", &self.pipeline)
+            .field("fallback", &self.fallback)
+            .field("custom", &self.custom)
+            .finish()
+    }
+}
+
+/// Builds a [`Detector`], starting from the default settings.
+///
+/// Types are given by name or alias. [`build`](DetectorBuilder::build) fails with
+/// [`Error::UnknownMimeType`] if one of them is not in the database.
+///
+/// # Examples
+/// ```rust
+/// use tika_magic::DetectorBuilder;
+///
+/// // Check for MP4 first, and skip the executables check
+/// let detector = DetectorBuilder::new()
+///     .add_priority_type("video/mp4")
+///     .remove_priority_type("application/x-dosexec")
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(detector.priority_types()[0], "video/mp4");
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct DetectorBuilder {
+    detector: Detector,
+    unknown: Option<String>,
+    invalid: Option<String>,
+}
+
+impl DetectorBuilder {
+    /// Starts from the default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Looks up a type, remembering the first unknown one for `build`
+    fn resolve(&mut self, mimetype: &str) -> Option<&'static dyn MimeTypeChecker> {
+        let checker = registry::resolve(mimetype);
+        if checker.is_none() && self.unknown.is_none() {
+            self.unknown = Some(mimetype.to_string());
+        }
+
+        checker
+    }
+
+    /// Sets how many bytes are read from the start of a file or stream, 128 KiB by
+    /// default.
+    ///
+    /// Container formats and types whose magic is far into the file may not be
+    /// recognized from a shorter head.
+    pub fn max_bytes(mut self, max_bytes: usize) -> Self {
+        self.detector.max_bytes = max_bytes;
+        self
+    }
+
+    /// Sets whether ZIP archives are opened to tell their formats apart, on by
+    /// default.
+    ///
+    /// Has no effect when the crate is built without the `open_zips` feature.
+    pub fn open_zips(mut self, open_zips: bool) -> Self {
+        self.detector.open_zips = open_zips;
+        self
+    }
+
+    /// Sets whether OLE compound documents are opened to tell their formats apart,
+    /// on by default.
+    ///
+    /// Has no effect when the crate is built without the `open_ole` feature.
+    pub fn open_ole(mut self, open_ole: bool) -> Self {
+        self.detector.open_ole = open_ole;
+        self
+    }
+
+    /// Adds a type to the front of the types checked before the full list.
+    ///
+    /// A type that is already in the list is moved to the front.
+    pub fn add_priority_type(mut self, mimetype: &str) -> Self {
+        if let Some(checker) = self.resolve(mimetype) {
+            let priority = self.detector.priority.to_mut();
+            priority.retain(|m| m.get_mime() != checker.get_mime());
+            priority.insert(0, checker);
+        }
+
+        self
+    }
+
+    /// Removes a type from the types checked before the full list.
+    ///
+    /// The type is still checked in its place in the full list.
+    pub fn remove_priority_type(mut self, mimetype: &str) -> Self {
+        if let Some(checker) = self.resolve(mimetype) {
+            self.detector
+                .priority
+                .to_mut()
+                .retain(|m| m.get_mime() != checker.get_mime());
+        }
+
+        self
+    }
+
+    /// Removes every type from the types checked before the full list.
+    pub fn clear_priority_types(mut self) -> Self {
+        self.detector.priority = Cow::Borrowed(&[]);
+        self
+    }
+
+    /// Sets the stages of the detection and the order they run in.
+    ///
+    /// Each stage after the first can only make the answer more specific, see
+    /// [`PipelineStage`]. Leaving a stage out skips it, so for example a pipeline
+    /// without [`PipelineStage::Name`] ignores the names given to `from_u8_with_name`
+    /// and friends.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use tika_magic::{Detector, PipelineStage};
+    ///
+    /// // Load a CSV file with no magic of its own
+    /// let input = b"a,b,c\n1,2,3\n";
+    ///
+    /// // Plain text first, then the file name
+    /// let detector = Detector::builder()
+    ///     .pipeline(&[PipelineStage::Magic, PipelineStage::Text, PipelineStage::Name])
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(tika_magic::from_u8(input), "application/octet-stream");
+    /// assert_eq!(detector.from_u8(input), "text/plain");
+    /// assert_eq!(detector.from_u8_with_name(input, "sizes.csv"), "text/csv");
+    /// ```
+    pub fn pipeline(mut self, stages: &[PipelineStage]) -> Self {
+        self.detector.pipeline = Cow::Owned(stages.to_vec());
+        self
+    }
+
+    /// Sets the type returned when nothing matches, `application/octet-stream` by
+    /// default.
+    pub fn fallback(mut self, mimetype: &str) -> Self {
+        if let Some(checker) = self.resolve(mimetype) {
+            self.detector.fallback = checker.get_mime();
+        }
+
+        self
+    }
+
+    /// Adds MIME type definitions loaded at runtime, checked at the given place
+    /// relative to the built-in types.
+    ///
+    /// Definitions added later with the same placement are checked after those
+    /// added earlier. Whatever the placement, a loaded type that is a `sub-class-of`
+    /// the detected type is checked next, like built-in subtypes are.
+    ///
+    /// Requires the `custom_mimetypes` feature.
+    #[cfg(feature = "custom_mimetypes")]
+    pub fn add_definitions(mut self, definitions: Definitions, placement: Placement) -> Self {
+        for detector in definitions.into_type_detectors() {
+            self.detector.custom.add(detector, placement);
+        }
+
+        self
+    }
+
+    /// Registers a detector for a type of your own, checked at the given place
+    /// relative to the built-in types.
+    ///
+    /// Detectors registered later with the same placement are checked after those
+    /// registered earlier. Whatever the placement, the detector is also checked once
+    /// one of its [`parents`](TypeDetector::parents) was detected. A detector whose
+    /// [`mime`](TypeDetector::mime) is not a valid type makes [`build`](Self::build)
+    /// fail.
+    pub fn add_type_detector<T: TypeDetector + 'static>(
+        mut self,
+        detector: T,
+        placement: Placement,
+    ) -> Self {
+        if MediaType::parse(detector.mime()).is_err() {
+            self.invalid
+                .get_or_insert_with(|| detector.mime().to_string());
+            return self;
+        }
+
+        #[cfg(any(feature = "custom_mimetypes", feature = "serde"))]
+        registry::register(detector.mime());
+        self.detector.custom.add(Arc::new(detector), placement);
+        self
+    }
+
+    /// Builds the detector.
+    ///
+    /// Fails with [`Error::InvalidMimeType`] for the first detector registered with
+    /// [`add_type_detector`](Self::add_type_detector) whose name is not a valid
+    /// type, then with [`Error::UnknownMimeType`] for the first type given to the
+    /// builder that is not in the database.
+    pub fn build(self) -> Result<Detector, Error> {
+        if let Some(mimetype) = self.invalid {
+            return Err(Error::InvalidMimeType(mimetype));
+        }
+
+        match self.unknown {
+            Some(mimetype) => Err(Error::UnknownMimeType(mimetype)),
+            None => Ok(self.detector),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "all_types"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detector_is_shareable() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Detector>();
+
+        let detector = std::sync::Arc::new(Detector::new());
+        let data = include_bytes!("../tests/inputs/image/png/png.png");
+        let handles = (0..4)
+            .map(|_| {
+                let detector = detector.clone();
+                std::thread::spawn(move || detector.from_u8(data))
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), "image/png");
+        }
+    }
+
+    #[test]
+    fn test_default_matches_free_functions() {
+        let data = include_bytes!("../tests/inputs/video/mp4/mp4.mp4");
+        let detector = Detector::default();
+        assert_eq!(detector.from_u8(data), crate::from_u8(data));
+        assert_eq!(detector.priority_types()[0], "image/png");
+        assert_eq!(detector.fallback(), "application/octet-stream");
+    }
+
+    #[test]
+    fn test_priority_types() {
+        let detector = Detector::builder()
+            .add_priority_type("text/xml")
+            .add_priority_type("image/png")
+            .remove_priority_type("image/gif")
+            .build()
+            .unwrap();
+        assert_eq!(
+            detector.priority_types(),
+            [
+                "image/png",
+                "application/xml",
+                "image/jpeg",
+                "application/zip",
+                "application/pdf",
+                "application/x-dosexec"
+            ]
+        );
+
+        // The priority list is a fast path, it does not change the answer
+        let data = include_bytes!("../tests/inputs/image/gif/gif.gif");
+        let detector = Detector::builder().clear_priority_types().build().unwrap();
+        assert!(detector.priority_types().is_empty());
+        assert_eq!(detector.from_u8(data), "image/gif");
+    }
+
+    #[test]
+    fn test_unknown_types() {
+        let result = Detector::builder()
+            .add_priority_type("image/x-made-up")
+            .fallback("text/x-also-made-up")
+            .build();
+        assert!(matches!(result, Err(Error::UnknownMimeType(m)) if m == "image/x-made-up"));
+    }
+
+    #[test]
+    fn test_invalid_type_detector() {
+        struct Nameless;
+        impl crate::TypeDetector for Nameless {
+            fn mime(&self) -> Mime {
+                "not a type"
+            }
+            fn check(&self, _bytes: &[u8]) -> bool {
+                true
+            }
+        }
+
+        let result = Detector::builder()
+            .add_type_detector(Nameless, crate::Placement::BeforeBuiltin)
+            .add_priority_type("image/x-made-up")
+            .build();
+        assert!(matches!(result, Err(Error::InvalidMimeType(m)) if m == "not a type"));
+    }
+
+    #[test]
+    fn test_fallback() {
+        let detector = Detector::builder().fallback("text/plain").build().unwrap();
+        let data = [0xfa, 0xce, 0xb0, 0x0c, 0x00, 0x17];
+        assert_eq!(detector.from_u8(&data), "text/plain");
+        assert_eq!(
+            detector.from_reader(io::Cursor::new(data)),
+            Some("text/plain")
+        );
+        assert!(!detector.match_u8_or_subtype("text/plain", &data));
+    }
+
+    #[test]
+    fn test_max_bytes() {
+        let path = Path::new("./tests/inputs/application/onix-message+xml/testONIXMessage.xml");
+        let detector = Detector::builder().max_bytes(5).build().unwrap();
+        assert_eq!(detector.from_filepath(path), Some("application/xml"));
+        assert_eq!(detector.try_from_filepath(path).unwrap().bytes_read(), 5);
+        assert_eq!(
+            crate::from_filepath(path),
+            Some("application/onix-message+xml")
+        );
+    }
+
+    #[test]
+    fn test_pipeline() {
+        let svg = include_bytes!("../tests/inputs/image/svg+xml/svg.svg");
+        assert_eq!(Detector::new().pipeline(), DEFAULT_PIPELINE);
+        assert_eq!(crate::from_u8(svg), "image/svg+xml");
+
+        // Without magic, the root element alone finds the type
+        let xml_root = Detector::builder()
+            .pipeline(&[PipelineStage::XmlRoot])
+            .build()
+            .unwrap();
+        assert_eq!(xml_root.from_u8(svg), "image/svg+xml");
+
+        // The name can only make the answer more specific
+        let detector = Detector::builder()
+            .pipeline(&[PipelineStage::Name, PipelineStage::Magic])
+            .build()
+            .unwrap();
+        assert_eq!(
+            detector.from_u8_with_name(svg, "drawing.xml"),
+            "image/svg+xml"
+        );
+        assert_eq!(detector.from_u8_with_name(svg, "drawing.png"), "image/png");
+        assert_eq!(
+            crate::from_u8_with_name(svg, "drawing.png"),
+            "image/svg+xml"
+        );
+
+        let nothing = Detector::builder().pipeline(&[]).build().unwrap();
+        assert_eq!(nothing.from_u8(svg), "application/octet-stream");
+    }
+
+    #[cfg(feature = "open_zips")]
+    #[test]
+    fn test_pipeline_containers() {
+        let data = include_bytes!(
+            "../tests/inputs/application/vnd.oasis.opendocument.text/testODTStyles3.odt"
+        );
+        let pipeline =
+            |stages: &[PipelineStage]| Detector::builder().pipeline(stages).build().unwrap();
+
+        let magic_first = pipeline(&[PipelineStage::Magic, PipelineStage::Container]);
+        assert_eq!(
+            magic_first.from_u8(data),
+            "application/vnd.oasis.opendocument.text"
+        );
+
+        let no_containers = pipeline(&[PipelineStage::Magic]);
+        assert_eq!(no_containers.from_u8(data), "application/zip");
+        assert!(!no_containers.match_u8("application/vnd.oasis.opendocument.text", data));
+    }
+
+    #[cfg(feature = "open_zips")]
+    #[test]
+    fn test_containers_toggle() {
+        let data = include_bytes!(
+            "../tests/inputs/application/vnd.oasis.opendocument.text/testODTStyles3.odt"
+        );
+        let detector = Detector::builder().open_zips(false).build().unwrap();
+        assert!(!detector.open_zips());
+        assert_eq!(detector.from_u8(data), "application/zip");
+        assert_eq!(
+            crate::from_u8(data),
+            "application/vnd.oasis.opendocument.text"
+        );
+    }
+}
@@ -0,0 +1,51 @@
+use std::fmt;
+use std::io;
+
+/// The ways a `try_*` detection can fail.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// The input could not be opened or read.
+    Io(io::Error),
+    /// The input has no bytes to detect.
+    Empty,
+    /// No type matched the input, detection would fall back to
+    /// `application/octet-stream`.
+    Unidentified,
+    /// The requested MIME type is not in the database.
+    UnknownMimeType(String),
+    /// A MIME type name given to a `Detector` is not a valid `type/subtype`.
+    InvalidMimeType(String),
+    /// MIME type definitions loaded at runtime could not be parsed.
+    InvalidDefinitions(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "I/O error: {e}"),
+            Error::Empty => f.write_str("the input is empty"),
+            Error::Unidentified => f.write_str("no MIME type matched the input"),
+            Error::UnknownMimeType(mimetype) => write!(f, "unknown MIME type: {mimetype}"),
+            Error::InvalidMimeType(mimetype) => write!(f, "invalid MIME type: {mimetype:?}"),
+            Error::InvalidDefinitions(reason) => {
+                write!(f, "invalid MIME type definitions: {reason}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
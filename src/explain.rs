@@ -0,0 +1,415 @@
+use crate::detector::DEFAULT_DETECTOR;
+use crate::magic::{MimeTypeChecker, RangeHits, ZipSpecialHandler};
+use crate::{registry, Detector, Mime};
+use std::fmt;
+
+/// The step of the detection that produced a type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+#[non_exhaustive]
+pub enum Stage {
+    /// The ZIP special handler recognized the archive contents.
+    ZipContainer,
+    /// The OLE special handler recognized the compound document contents.
+    OleContainer,
+    /// A type from the short list of common types that are checked first.
+    PriorityList,
+    /// A type from the full list of types, ordered by magic priority.
+    MainList,
+    /// A `root-XML` rule matched the root element of an XML document.
+    XmlRoot,
+    /// The text heuristics recognized plain text.
+    Text,
+    /// The file name matched one of the type's glob patterns.
+    Name,
+    /// A type registered on the detector matched.
+    Custom,
+    /// Nothing matched, so the fallback type was returned.
+    Fallback,
+}
+
+impl Stage {
+    /// The container stage that recognized a head
+    pub(crate) fn container(head: &[u8]) -> Stage {
+        if head.starts_with(ZipSpecialHandler::MAGIC) {
+            Stage::ZipContainer
+        } else {
+            Stage::OleContainer
+        }
+    }
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Stage::ZipContainer => "ZIP container",
+            Stage::OleContainer => "OLE container",
+            Stage::PriorityList => "priority list",
+            Stage::MainList => "main list",
+            Stage::XmlRoot => "XML root element",
+            Stage::Text => "text heuristics",
+            Stage::Name => "file name",
+            Stage::Custom => "registered types",
+            Stage::Fallback => "fallback",
+        })
+    }
+}
+
+/// A magic primitive that matched, such as `offset 0 == "PK\x03\x04"`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RuleMatch {
+    #[cfg_attr(
+        feature = "serde",
+        serde(deserialize_with = "crate::serialization::mime")
+    )]
+    mime: Mime,
+    rule: String,
+    offset: usize,
+}
+
+impl RuleMatch {
+    /// The type the rule belongs to.
+    pub fn mime(&self) -> Mime {
+        self.mime
+    }
+
+    /// The rule, written like `offset 0:256 contains "<tmx"`.
+    pub fn rule(&self) -> &str {
+        &self.rule
+    }
+
+    /// Where in the bytes the rule matched.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl fmt::Display for RuleMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {}, matched at offset {}",
+            self.mime, self.rule, self.offset
+        )
+    }
+}
+
+/// A type a step of the detection found, with the type whose walk found it when
+/// that was a magic list.
+#[derive(Clone, Copy)]
+pub(crate) struct Found {
+    pub(crate) mime: Mime,
+    pub(crate) stage: Stage,
+    pub(crate) checker: Option<&'static dyn MimeTypeChecker>,
+}
+
+impl Found {
+    pub(crate) fn new(mime: Mime, stage: Stage) -> Self {
+        Found {
+            mime,
+            stage,
+            checker: None,
+        }
+    }
+
+    /// Found by walking down from a type of a magic list
+    pub(crate) fn walked(mime: Mime, stage: Stage, checker: &'static dyn MimeTypeChecker) -> Self {
+        Found {
+            mime,
+            stage,
+            checker: Some(checker),
+        }
+    }
+}
+
+/// Why [`from_u8`](crate::from_u8) returned the type it did, see [`explain`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Explanation {
+    #[cfg_attr(
+        feature = "serde",
+        serde(deserialize_with = "crate::serialization::mime")
+    )]
+    mime: Mime,
+    stage: Stage,
+    #[cfg_attr(
+        feature = "serde",
+        serde(deserialize_with = "crate::serialization::mimes")
+    )]
+    path: Vec<Mime>,
+    matches: Vec<RuleMatch>,
+}
+
+impl Explanation {
+    /// The detected type, the same one `from_u8` returns.
+    pub fn mime(&self) -> Mime {
+        self.mime
+    }
+
+    /// The step of the detection that produced the type.
+    pub fn stage(&self) -> Stage {
+        self.stage
+    }
+
+    /// The walk from the type that was checked down to the detected child type,
+    /// ending with the detected type.
+    ///
+    /// Empty for types found by a container handler, the text heuristics, the file
+    /// name, a registered type or the fallback.
+    pub fn path(&self) -> &[Mime] {
+        &self.path
+    }
+
+    /// The magic primitives that matched along the path, parents first.
+    pub fn matches(&self) -> &[RuleMatch] {
+        &self.matches
+    }
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} from the {}", self.mime, self.stage)?;
+        if !self.path.is_empty() {
+            write!(f, "\n  path: {}", self.path.join(" -> "))?;
+        }
+        for rule_match in &self.matches {
+            write!(f, "\n  {rule_match}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Explanation {
+    /// Explains a detection from the last step that made its answer more specific,
+    /// None if no step found anything.
+    ///
+    /// `whole` tells whether the bytes are the whole input, see [`RangeHits::whole`].
+    pub(crate) fn new(found: Option<Found>, fallback: Mime, bytes: &[u8], whole: bool) -> Self {
+        let Some(found) = found else {
+            return Explanation {
+                mime: fallback,
+                stage: Stage::Fallback,
+                path: vec![],
+                matches: vec![],
+            };
+        };
+
+        let checker = match found.stage {
+            Stage::XmlRoot => registry::resolve(found.mime),
+            _ => found.checker,
+        };
+        let hits = RangeHits::new(bytes).whole(whole);
+        let (path, matches) = checker
+            .and_then(|checker| explain_recursive(checker, bytes, &hits))
+            .unwrap_or_default();
+
+        Explanation {
+            mime: found.mime,
+            stage: found.stage,
+            path,
+            matches,
+        }
+    }
+}
+
+/// Mirrors `check_recursive`, keeping the path and the primitives that matched.
+fn explain_recursive(
+    checker: &'static dyn MimeTypeChecker,
+    bytes: &[u8],
+    hits: &RangeHits,
+) -> Option<(Vec<Mime>, Vec<RuleMatch>)> {
+    let matches = checker.check_with(bytes, hits);
+    if !matches && !checker.is_virtual() {
+        return None;
+    }
+
+    let mut rule_hits = vec![];
+    if matches {
+        checker
+            .get_rule()
+            .explain(bytes, hits.is_whole(), &mut rule_hits);
+    }
+    let own = rule_hits.into_iter().map(|(rule, offset)| RuleMatch {
+        mime: checker.get_mime(),
+        rule: rule.to_string(),
+        offset,
+    });
+
+    for child in checker.get_children() {
+        if let Some((mut path, child_matches)) = explain_recursive(*child, bytes, hits) {
+            path.insert(0, checker.get_mime());
+            return Some((path, own.chain(child_matches).collect()));
+        }
+    }
+
+    matches.then(|| (vec![checker.get_mime()], own.collect()))
+}
+
+impl Detector {
+    /// Explains how the MIME type of a byte stream is detected.
+    ///
+    /// See [`explain`](crate::explain).
+    pub fn explain(&self, bytes: &[u8]) -> Explanation {
+        let mut last = None;
+        self.run_recorded(
+            bytes,
+            false,
+            None,
+            || self.handle_special_files(bytes),
+            &mut |found| last = Some(found),
+        );
+
+        Explanation::new(last, self.fallback(), bytes, false)
+    }
+}
+
+/// Explains how the MIME type of a byte stream is detected.
+///
+/// Runs the same detection as [`from_u8`](crate::from_u8) and reports which step
+/// produced the type: a ZIP or OLE container handler, the priority list or the main
+/// list, along with the parent to child walk and the magic rules that matched, or
+/// another stage of the [pipeline](crate::PipelineStage).
+///
+/// # Examples
+/// ```rust
+/// use tika_magic::Stage;
+///
+/// // Load a GIF file
+/// let input: &[u8] = include_bytes!("../tests/inputs/image/gif/gif.gif");
+///
+/// let explanation = tika_magic::explain(input);
+/// assert_eq!(explanation.mime(), "image/gif");
+/// assert_eq!(explanation.stage(), Stage::PriorityList);
+/// assert_eq!(explanation.matches()[0].rule(), "offset 0 == \"GIF89a\"");
+///
+/// // Prints every step, for example when triaging a misdetection
+/// println!("{explanation}");
+/// ```
+pub fn explain(bytes: &[u8]) -> Explanation {
+    DEFAULT_DETECTOR.explain(bytes)
+}
+
+#[cfg(all(test, feature = "all_types"))]
+mod tests {
+    use super::*;
+    use crate::{PipelineStage, Placement, TypeDetector};
+
+    #[test]
+    fn test_explain_priority_list() {
+        let data = include_bytes!("../tests/inputs/image/png/png.png");
+        let explanation = explain(data);
+        assert_eq!(explanation.mime(), "image/png");
+        assert_eq!(explanation.stage(), Stage::PriorityList);
+        assert_eq!(explanation.path(), ["image/png"]);
+        assert_eq!(explanation.matches()[0].offset(), 0);
+    }
+
+    #[test]
+    fn test_explain_child_walk() {
+        let data =
+            include_bytes!("../tests/inputs/application/onix-message+xml/testONIXMessage.xml");
+        let explanation = explain(data);
+        assert_eq!(explanation.mime(), crate::from_u8(data));
+        assert_eq!(explanation.stage(), Stage::MainList);
+        assert_eq!(
+            explanation.path(),
+            ["application/xml", "application/onix-message+xml"]
+        );
+        assert_eq!(explanation.matches()[0].mime(), "application/xml");
+        assert_eq!(explanation.matches()[0].rule(), "offset 0 == \"<?xml\"");
+        assert!(explanation
+            .matches()
+            .iter()
+            .any(|m| m.mime() == "application/onix-message+xml"));
+    }
+
+    #[test]
+    fn test_explain_fallback() {
+        let explanation = explain(&[0xfa, 0xce, 0xb0, 0x0c, 0x00, 0x17]);
+        assert_eq!(explanation.mime(), "application/octet-stream");
+        assert_eq!(explanation.stage(), Stage::Fallback);
+        assert!(explanation.path().is_empty());
+        assert_eq!(
+            explanation.to_string(),
+            "application/octet-stream from the fallback"
+        );
+    }
+
+    #[cfg(feature = "open_zips")]
+    #[test]
+    fn test_explain_zip_container() {
+        let data = include_bytes!("../tests/inputs/application/vnd.openxmlformats-officedocument.wordprocessingml.document/testWORD.docx");
+        let explanation = explain(data);
+        assert_eq!(explanation.mime(), crate::from_u8(data));
+        assert_eq!(explanation.stage(), Stage::ZipContainer);
+    }
+
+    #[test]
+    fn test_detector_explain() {
+        let svg = include_bytes!("../tests/inputs/image/svg+xml/svg.svg");
+        let gif = include_bytes!("../tests/inputs/image/gif/gif.gif");
+
+        // The root element alone finds the type, with the rules of the type it found
+        let xml_root = Detector::builder()
+            .pipeline(&[PipelineStage::XmlRoot])
+            .build()
+            .unwrap();
+        let explanation = xml_root.explain(svg);
+        assert_eq!(explanation.mime(), xml_root.from_u8(svg));
+        assert_eq!(explanation.stage(), Stage::XmlRoot);
+        assert_eq!(explanation.path(), ["image/svg+xml"]);
+
+        // Without the priority list, the GIF comes from the main list
+        let detector = Detector::builder()
+            .clear_priority_types()
+            .fallback("text/plain")
+            .build()
+            .unwrap();
+        assert_eq!(detector.explain(gif).stage(), Stage::MainList);
+        let explanation = detector.explain(&[0xfa, 0xce, 0xb0, 0x0c]);
+        assert_eq!(explanation.mime(), "text/plain");
+        assert_eq!(explanation.stage(), Stage::Fallback);
+    }
+
+    #[test]
+    fn test_detector_explain_custom() {
+        struct Gif87;
+
+        impl TypeDetector for Gif87 {
+            fn mime(&self) -> Mime {
+                "image/x-gif87"
+            }
+
+            fn check(&self, bytes: &[u8]) -> bool {
+                bytes.starts_with(b"GIF87a")
+            }
+        }
+
+        let detector = Detector::builder()
+            .add_type_detector(Gif87, Placement::BeforeBuiltin)
+            .build()
+            .unwrap();
+        let explanation = detector.explain(b"GIF87a\x01\x00\x01\x00");
+        assert_eq!(explanation.mime(), "image/x-gif87");
+        assert_eq!(explanation.stage(), Stage::Custom);
+        assert!(explanation.matches().is_empty());
+    }
+
+    #[cfg(feature = "open_zips")]
+    #[test]
+    fn test_detector_explain_containers_toggle() {
+        let data = include_bytes!(
+            "../tests/inputs/application/vnd.oasis.opendocument.text/testODTStyles3.odt"
+        );
+        let detector = Detector::builder().open_zips(false).build().unwrap();
+        let explanation = detector.explain(data);
+        assert_eq!(explanation.mime(), "application/zip");
+        assert_ne!(explanation.stage(), Stage::ZipContainer);
+    }
+}
@@ -0,0 +1,224 @@
+use crate::magic::MIME_TYPES;
+use crate::registry::{normalize, resolve};
+use crate::Mime;
+use once_cell::sync::Lazy;
+use std::collections::{HashMap, VecDeque};
+
+const OCTET_STREAM: Mime = "application/octet-stream";
+
+/// The supertypes of every built-in type, which `is_a` looks up rather than
+/// collecting them on every call
+static SUPERTYPES: Lazy<HashMap<Mime, Vec<Mime>>> = Lazy::new(|| {
+    MIME_TYPES
+        .iter()
+        .map(|checker| (checker.get_mime(), supertypes(checker.get_mime())))
+        .collect()
+});
+
+/// The parents Tika assumes for types that do not declare any, see
+/// `MediaTypeRegistry.getSupertype`.
+fn implicit_supertypes(mimetype: &str) -> Vec<Mime> {
+    if let Some((base, _)) = mimetype.split_once(';') {
+        return match resolve(base) {
+            Some(checker) => vec![checker.get_mime()],
+            None => implicit_supertypes(base),
+        };
+    }
+
+    let Some((top, sub)) = mimetype.split_once('/') else {
+        return vec![];
+    };
+
+    if sub.ends_with("+xml") {
+        vec!["application/xml"]
+    } else if sub.ends_with("+zip") {
+        vec!["application/zip"]
+    } else if top == "text" && mimetype != "text/plain" {
+        vec!["text/plain"]
+    } else if mimetype != OCTET_STREAM {
+        vec![OCTET_STREAM]
+    } else {
+        vec![]
+    }
+}
+
+fn direct_supertypes(mimetype: &str) -> Vec<Mime> {
+    let Some(checker) = resolve(mimetype) else {
+        return implicit_supertypes(&normalize(mimetype));
+    };
+
+    let mime = checker.get_mime();
+    let declared = checker
+        .get_parents()
+        .iter()
+        .filter_map(|parent| resolve(parent))
+        .map(|parent| parent.get_mime())
+        .filter(|parent| *parent != mime)
+        .collect::<Vec<_>>();
+
+    if declared.is_empty() {
+        implicit_supertypes(mime)
+    } else {
+        declared
+    }
+}
+
+/// Gets all the supertypes of a MIME type, nearest first.
+///
+/// Follows the `<sub-class-of>` declarations of the Tika database. Types that do not
+/// declare a parent get the implicit ones Tika uses: a type with parameters is a
+/// subtype of the type without them, every `+xml` type is an `application/xml`,
+/// every `+zip` type is an `application/zip`, every `text/*` type is a `text/plain`
+/// and everything is an `application/octet-stream`, which always comes last.
+///
+/// The given type itself is not included. Aliases are resolved to their canonical
+/// type first.
+///
+/// # Examples
+/// ```rust
+/// assert_eq!(
+///     tika_magic::supertypes("application/java-archive"),
+///     vec!["application/zip", "application/octet-stream"]
+/// );
+/// assert_eq!(
+///     tika_magic::supertypes("text/csv"),
+///     vec!["text/plain", "application/octet-stream"]
+/// );
+/// assert!(tika_magic::supertypes("application/octet-stream").is_empty());
+/// ```
+pub fn supertypes(mimetype: &str) -> Vec<Mime> {
+    let own = resolve(mimetype).map(|checker| checker.get_mime());
+
+    let mut result: Vec<Mime> = vec![];
+    let mut pending = VecDeque::from(direct_supertypes(mimetype));
+    while let Some(mime) = pending.pop_front() {
+        if result.contains(&mime) || own == Some(mime) {
+            continue;
+        }
+
+        result.push(mime);
+        pending.extend(direct_supertypes(mime));
+    }
+
+    if let Some(i) = result.iter().position(|mime| *mime == OCTET_STREAM) {
+        let octet_stream = result.remove(i);
+        result.push(octet_stream);
+    }
+
+    result
+}
+
+/// Checks if a MIME type is the same as, or a subtype of, another MIME type.
+///
+/// Both types may be aliases. See [`supertypes`] for how the hierarchy is built.
+///
+/// # Examples
+/// ```rust
+/// // Office Open XML documents are ZIP files
+/// assert!(tika_magic::is_a(
+///     "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+///     "application/zip"
+/// ));
+///
+/// // Every XML based format is XML, and every type is a type of itself
+/// assert!(tika_magic::is_a("image/svg+xml", "application/xml"));
+/// assert!(tika_magic::is_a("text/xml", "application/xml"));
+///
+/// assert!(!tika_magic::is_a("image/png", "application/zip"));
+/// ```
+pub fn is_a(child: &str, parent: &str) -> bool {
+    let parent_normalized;
+    let parent = match resolve(parent) {
+        Some(checker) => checker.get_mime(),
+        None => {
+            parent_normalized = normalize(parent);
+            &parent_normalized
+        }
+    };
+
+    match resolve(child) {
+        Some(checker) => {
+            let child = checker.get_mime();
+            child == parent || SUPERTYPES.get(child).is_some_and(|s| s.contains(&parent))
+        }
+        None => normalize(child) == parent || supertypes(child).contains(&parent),
+    }
+}
+
+#[cfg(all(test, feature = "all_types"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_declared_supertypes() {
+        assert_eq!(
+            supertypes("application/vnd.openxmlformats-officedocument.wordprocessingml.document"),
+            vec![
+                "application/x-tika-ooxml",
+                "application/zip",
+                "application/octet-stream"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parameter_supertypes() {
+        let dita = supertypes("application/dita+xml;format=concept");
+        assert_eq!(dita[0], "application/dita+xml;format=topic");
+        assert!(dita.contains(&"application/xml"));
+        assert!(dita.contains(&"text/plain"));
+        assert_eq!(dita.last(), Some(&"application/octet-stream"));
+
+        // Parameters that are not part of a known type are ignored
+        assert_eq!(
+            supertypes("text/html; charset=utf-8"),
+            supertypes("text/html")
+        );
+    }
+
+    #[test]
+    fn test_implicit_supertypes_of_unknown_types() {
+        assert_eq!(
+            supertypes("application/x-made-up+xml"),
+            vec!["application/xml", "text/plain", "application/octet-stream"]
+        );
+        assert_eq!(
+            supertypes("application/x-made-up+zip"),
+            vec!["application/zip", "application/octet-stream"]
+        );
+        assert_eq!(
+            supertypes("text/x-made-up"),
+            vec!["text/plain", "application/octet-stream"]
+        );
+        assert_eq!(
+            supertypes("application/x-made-up"),
+            vec!["application/octet-stream"]
+        );
+        assert!(supertypes("not a mime type").is_empty());
+    }
+
+    #[test]
+    fn test_is_a() {
+        assert!(is_a("application/java-archive", "application/zip"));
+        assert!(is_a(
+            "application/java-archive",
+            "application/x-zip-compressed"
+        ));
+        assert!(is_a("text/csv", "text/plain"));
+        assert!(is_a("image/png", "application/octet-stream"));
+        assert!(is_a("application/x-made-up", "application/x-made-up"));
+        assert!(is_a("Application/X-Made-Up", "application/x-made-up"));
+
+        assert!(!is_a("application/zip", "application/java-archive"));
+        assert!(!is_a("text/plain", "text/csv"));
+        assert!(!is_a("application/octet-stream", "image/png"));
+        assert!(!is_a("application/x-made-up", "application/x-also-made-up"));
+    }
+
+    #[test]
+    fn test_every_known_type_is_an_octet_stream() {
+        for t in crate::registry::all() {
+            assert!(is_a(t.mime(), OCTET_STREAM), "{}", t.mime());
+        }
+    }
+}
@@ -8,11 +8,48 @@
 //! assert_eq!(result, true);
 //! ```
 
+mod allowlist;
+#[cfg(feature = "tokio")]
+mod async_io;
+mod candidates;
+#[cfg(feature = "custom_mimetypes")]
+mod definitions;
+mod detection;
+mod detector;
+mod error;
+mod explain;
+mod hierarchy;
 mod magic;
-
-use crate::magic::{MIME_MAP, MIME_TYPES, PRIORITY_MIME_TYPES};
+mod media_type;
+mod pipeline;
+pub mod registry;
+#[cfg(feature = "serde")]
+mod serialization;
+mod type_detector;
+mod verify;
+
+pub use crate::allowlist::detect_among;
+#[cfg(feature = "tokio")]
+pub use crate::async_io::{from_async_path, from_async_reader};
+pub use crate::candidates::{
+    from_filepath_ranked, from_u8_ranked, from_u8_ranked_with_name, Candidate, Source,
+};
+#[cfg(feature = "custom_mimetypes")]
+pub use crate::definitions::Definitions;
+pub use crate::detection::Detection;
+pub use crate::detector::{Detector, DetectorBuilder};
+pub use crate::error::Error;
+pub use crate::explain::{explain, Explanation, RuleMatch, Stage};
+pub use crate::hierarchy::{is_a, supertypes};
+pub use crate::media_type::{MediaType, ParseMediaTypeError};
+pub use crate::pipeline::PipelineStage;
+pub use crate::registry::{acronym, description, links, uti};
+pub use crate::type_detector::{Placement, TypeDetector};
+pub use crate::verify::{verify, Verdict, Verification};
+
+use crate::detector::DEFAULT_DETECTOR;
 use std::fs::File;
-use std::io::Read;
+use std::io::{self, Read, Seek};
 use std::path::Path;
 
 pub type Mime = &'static str;
@@ -22,6 +59,7 @@ pub type Mime = &'static str;
 /// Returns true or false if it matches or not. If the given MIME type is not known,
 /// the function will always return false.
 /// If mimetype is an alias of a known MIME, the file will be checked against that MIME.
+/// The name is resolved like [`canonicalize`] does, ignoring case and parameters.
 ///
 /// # Examples
 /// ```rust
@@ -33,29 +71,38 @@ pub type Mime = &'static str;
 /// assert_eq!(result, true);
 /// ```
 pub fn match_u8(mimetype: &str, bytes: &[u8]) -> bool {
-    if handle_special_files(bytes).is_some() {
-        return true;
-    }
-
-    let Some(mm) = MIME_MAP.get(mimetype) else {
-        return false;
-    };
-
-    for m in mm.iter() {
-        if m.check(bytes) {
-            return true;
-        }
-    }
+    DEFAULT_DETECTOR.match_u8(mimetype, bytes)
+}
 
-    false
+/// Checks if the given bytestream matches the given MIME type or one of its subtypes.
+///
+/// This is [`match_u8`], but also returns true when the bytestream is detected as a
+/// type that [`is_a`] the given MIME type. Asking for `application/zip` matches any
+/// ZIP based format, such as an Office Open XML document or a JAR.
+///
+/// # Examples
+/// ```rust
+/// // Load a DOCX file
+/// let input: &[u8] = include_bytes!("../tests/inputs/application/vnd.openxmlformats-officedocument.wordprocessingml.document/testWORD.docx");
+///
+/// // A DOCX is a ZIP, but not a PNG
+/// assert!(tika_magic::match_u8_or_subtype("application/zip", input));
+/// assert!(!tika_magic::match_u8_or_subtype("image/png", input));
+/// ```
+pub fn match_u8_or_subtype(mimetype: &str, bytes: &[u8]) -> bool {
+    DEFAULT_DETECTOR.match_u8_or_subtype(mimetype, bytes)
 }
 
-fn check_recursive(checker: &'static dyn magic::MimeTypeChecker, bytes: &[u8]) -> Option<Mime> {
-    let matches = checker.check(bytes);
+fn check_recursive(
+    checker: &'static dyn magic::MimeTypeChecker,
+    bytes: &[u8],
+    hits: &magic::RangeHits,
+) -> Option<Mime> {
+    let matches = checker.check_with(bytes, hits);
     if matches || checker.is_virtual() {
         let children = checker.get_children();
         for child in children {
-            if let Some(mime) = check_recursive(*child, bytes) {
+            if let Some(mime) = check_recursive(*child, bytes, hits) {
                 return Some(mime);
             }
         }
@@ -68,39 +115,81 @@ fn check_recursive(checker: &'static dyn magic::MimeTypeChecker, bytes: &[u8]) -
     None
 }
 
+/// Whether [`check_recursive`] finds a type, once more bytes can no longer change
+/// that. None while they still could.
+#[cfg(feature = "tokio")]
+fn settled_recursive(
+    checker: &'static dyn magic::MimeTypeChecker,
+    bytes: &[u8],
+    hits: &magic::RangeHits,
+) -> Option<bool> {
+    let matches = checker.check_with(bytes, hits);
+    if !matches && !checker.get_rule().never_matches(bytes, hits) {
+        return None;
+    }
+
+    if matches || checker.is_virtual() {
+        for child in checker.get_children() {
+            if settled_recursive(*child, bytes, hits)? {
+                return Some(true);
+            }
+        }
+    }
+
+    Some(matches)
+}
+
+/// The type a container handler found, if it is one of the selected families
+#[cfg(any(feature = "open_zips", feature = "open_ole"))]
+fn kept(mime: Option<Mime>) -> Option<Mime> {
+    if cfg!(feature = "all_types") {
+        return mime;
+    }
+    mime.filter(|mime| registry::resolve(mime).is_some())
+}
+
 #[cfg(feature = "open_zips")]
 fn maybe_open_zip(bytes: &[u8]) -> Option<Mime> {
-    crate::magic::ZipSpecialHandler.check(bytes)
+    kept(crate::magic::ZipSpecialHandler.check(bytes))
 }
 #[cfg(not(feature = "open_zips"))]
 fn maybe_open_zip(_bytes: &[u8]) -> Option<Mime> {
     None
 }
 
+#[cfg(feature = "open_zips")]
+fn maybe_open_zip_stream<R: Read + Seek>(head: &[u8], reader: R) -> Option<Mime> {
+    kept(crate::magic::ZipSpecialHandler.check_stream(head, reader))
+}
+#[cfg(not(feature = "open_zips"))]
+fn maybe_open_zip_stream<R: Read + Seek>(_head: &[u8], _reader: R) -> Option<Mime> {
+    None
+}
+
 #[cfg(feature = "open_ole")]
 fn maybe_open_ole(bytes: &[u8]) -> Option<Mime> {
-    crate::magic::OleSpecialHandler.check(bytes)
+    kept(crate::magic::OleSpecialHandler.check(bytes))
 }
 #[cfg(not(feature = "open_ole"))]
 fn maybe_open_ole(_bytes: &[u8]) -> Option<Mime> {
     None
 }
 
-fn handle_special_files(bytes: &[u8]) -> Option<Mime> {
-    if let Some(mime) = maybe_open_zip(bytes) {
-        return Some(mime);
-    }
-
-    if let Some(mime) = maybe_open_ole(bytes) {
-        return Some(mime);
-    }
-
+#[cfg(feature = "open_ole")]
+fn maybe_open_ole_stream<R: Read>(head: &[u8], reader: R) -> Option<Mime> {
+    kept(crate::magic::OleSpecialHandler.check_stream(head, reader))
+}
+#[cfg(not(feature = "open_ole"))]
+fn maybe_open_ole_stream<R: Read>(_head: &[u8], _reader: R) -> Option<Mime> {
     None
 }
 
 /// Gets the MIME from a byte stream.
 ///
-/// Returns MIME as string.
+/// Returns MIME as string. The bytes are taken as the start of the input, so a regex
+/// rule over a range of offsets only matches bytes that cover the whole range. The
+/// reader and file functions tell when they read all of a short input, and match
+/// such a rule against what there is of it, like Tika does.
 ///
 /// # Examples
 /// ```rust
@@ -112,28 +201,13 @@ fn handle_special_files(bytes: &[u8]) -> Option<Mime> {
 /// assert_eq!(result, "image/gif");
 /// ```
 pub fn from_u8(bytes: &[u8]) -> Mime {
-    if let Some(mime) = handle_special_files(bytes) {
-        return mime;
-    }
-
-    for m in PRIORITY_MIME_TYPES {
-        if let Some(mime) = check_recursive(*m, bytes) {
-            return mime;
-        }
-    }
-
-    for m in MIME_TYPES {
-        if let Some(mime) = check_recursive(*m, bytes) {
-            return mime;
-        }
-    }
-
-    "application/octet-stream"
+    DEFAULT_DETECTOR.from_u8(bytes)
 }
 
 /// Gets the MIME types that match a byte stream.
 ///
-/// Returns a vector of MIMEs.
+/// Returns a vector of MIMEs, in database order. See [`from_u8_ranked`] for a list
+/// that starts with the [`from_u8`] answer and is ordered by priority.
 ///
 /// # Examples
 /// ```rust
@@ -145,13 +219,7 @@ pub fn from_u8(bytes: &[u8]) -> Mime {
 /// assert_eq!(result, vec!["video/mp4", "video/quicktime"]);
 /// ```
 pub fn from_u8_exhaustive(bytes: &[u8]) -> Vec<Mime> {
-    MIME_TYPES
-        .iter()
-        .filter_map(|m| match m.check(bytes) {
-            true => Some(m.get_mime()),
-            false => None,
-        })
-        .collect()
+    DEFAULT_DETECTOR.from_u8_exhaustive(bytes)
 }
 
 /// Check if the given file matches the given MIME type.
@@ -168,14 +236,26 @@ pub fn from_u8_exhaustive(bytes: &[u8]) -> Vec<Mime> {
 /// assert_eq!(result, true);
 /// ```
 pub fn match_file(mimetype: &str, file: &File) -> bool {
-    let mut buf = [0u8; 0x20000];
-    match file.take(buf.len() as u64).read(&mut buf) {
-        Ok(0) => return false,
-        Err(_) => return false,
-        _ => (),
-    }
+    DEFAULT_DETECTOR.match_file(mimetype, file)
+}
 
-    match_u8(mimetype, &buf)
+/// Check if the given file matches the given MIME type or one of its subtypes.
+///
+/// See [`match_u8_or_subtype`].
+///
+/// # Examples
+/// ```rust
+/// use std::fs::File;
+///
+/// // Get path to a GIF file
+/// let file = File::open("./tests/inputs/image/gif/gif.gif").unwrap();
+///
+/// // Check if the file is a GIF or a more specific kind of GIF
+/// let result = tika_magic::match_file_or_subtype("image/gif", &file);
+/// assert_eq!(result, true);
+/// ```
+pub fn match_file_or_subtype(mimetype: &str, file: &File) -> bool {
+    DEFAULT_DETECTOR.match_file_or_subtype(mimetype, file)
 }
 
 /// Check if the file at the given path matches the given MIME type.
@@ -194,15 +274,93 @@ pub fn match_file(mimetype: &str, file: &File) -> bool {
 /// assert_eq!(result, true);
 /// ```
 pub fn match_filepath(mimetype: &str, path: &Path) -> bool {
-    match File::open(path) {
-        Ok(file) => match_file(mimetype, &file),
-        Err(_) => false,
-    }
+    DEFAULT_DETECTOR.match_filepath(mimetype, path)
+}
+
+/// Check if the file at the given path matches the given MIME type or one of its subtypes.
+///
+/// Returns false if the file could not be read or the given MIME type is not known.
+/// See [`match_u8_or_subtype`].
+///
+/// # Examples
+/// ```rust
+/// use std::path::Path;
+///
+/// // Get path to a DOCX file
+/// let path = Path::new("./tests/inputs/application/vnd.openxmlformats-officedocument.wordprocessingml.document/testWORD.docx");
+///
+/// // Check if the file is any kind of ZIP
+/// let result = tika_magic::match_filepath_or_subtype("application/zip", path);
+/// assert_eq!(result, true);
+/// ```
+pub fn match_filepath_or_subtype(mimetype: &str, path: &Path) -> bool {
+    DEFAULT_DETECTOR.match_filepath_or_subtype(mimetype, path)
+}
+
+/// How much of the start of a file or stream is read for detection.
+const HEAD_SIZE: usize = 0x20000;
+
+/// Reads up to `limit` bytes, retrying short reads until the head is full or the
+/// input ends.
+fn read_head<R: Read>(reader: R, limit: usize) -> io::Result<Vec<u8>> {
+    let mut head = Vec::with_capacity(limit.min(HEAD_SIZE));
+    reader.take(limit as u64).read_to_end(&mut head)?;
+    Ok(head)
+}
+
+/// Gets the MIME type of the data read from a reader.
+///
+/// Reads up to the first 128 KiB, so the reader is left somewhere past the point
+/// it started at. Returns None if nothing could be read.
+///
+/// # Examples
+/// ```rust
+/// use std::io::Cursor;
+///
+/// // Read a GIF file from memory
+/// let input = Cursor::new(include_bytes!("../tests/inputs/image/gif/gif.gif").to_vec());
+///
+/// // Find the MIME type of the GIF
+/// let result = tika_magic::from_reader(input);
+/// assert_eq!(result, Some("image/gif"));
+/// ```
+pub fn from_reader<R: Read>(reader: R) -> Option<Mime> {
+    DEFAULT_DETECTOR.from_reader(reader)
+}
+
+/// Gets the MIME type of the data in a seekable reader.
+///
+/// Like [`from_reader`], but ZIP and OLE containers are inspected using the whole
+/// stream rather than its first 128 KiB, so large archives whose directory is at
+/// the end are still recognized. The reader is put back where it started.
+///
+/// Returns None if nothing could be read.
+///
+/// # Examples
+/// ```rust
+/// use std::io::Cursor;
+///
+/// // Read a DOCX file from memory
+/// let input = include_bytes!("../tests/inputs/application/vnd.openxmlformats-officedocument.wordprocessingml.document/testWORD.docx");
+/// let mut reader = Cursor::new(input.to_vec());
+///
+/// // Find the MIME type of the DOCX, the ZIP directory is read from the end
+/// let result = tika_magic::from_seekable(&mut reader);
+/// # #[cfg(feature = "open_zips")]
+/// assert_eq!(
+///     result,
+///     Some("application/vnd.openxmlformats-officedocument.wordprocessingml.document")
+/// );
+/// assert_eq!(reader.position(), 0);
+/// ```
+pub fn from_seekable<R: Read + Seek>(reader: R) -> Option<Mime> {
+    DEFAULT_DETECTOR.from_seekable(reader)
 }
 
 /// Gets the MIME type for a file.
 ///
-/// Does not look at file name or extension, just the contents.
+/// Does not look at file name or extension, just the contents. Only the first
+/// 128 KiB are read, use [`from_seekable`] to inspect large containers.
 ///
 /// # Examples
 /// ```rust
@@ -216,15 +374,7 @@ pub fn match_filepath(mimetype: &str, path: &Path) -> bool {
 /// assert_eq!(result, Some("image/gif"));
 /// ```
 pub fn from_file(file: &File) -> Option<Mime> {
-    let mut buf = [0u8; 0x20000];
-
-    match file.take(buf.len() as u64).read(&mut buf) {
-        Ok(0) => return None,
-        Err(_) => return None,
-        _ => (),
-    }
-
-    Some(from_u8(&buf))
+    DEFAULT_DETECTOR.from_file(file)
 }
 
 /// Gets all the MIME types that match a file.
@@ -241,15 +391,7 @@ pub fn from_file(file: &File) -> Option<Mime> {
 /// assert_eq!(result, Some(vec!["video/mp4", "video/quicktime"]));
 /// ```
 pub fn from_file_exhaustive(file: &File) -> Option<Vec<Mime>> {
-    let mut buf = [0u8; 0x20000];
-
-    match file.take(buf.len() as u64).read(&mut buf) {
-        Ok(0) => return None,
-        Err(_) => return None,
-        _ => (),
-    }
-
-    Some(from_u8_exhaustive(&buf))
+    DEFAULT_DETECTOR.from_file_exhaustive(file)
 }
 
 /// Gets the MIME type for a path
@@ -269,10 +411,46 @@ pub fn from_file_exhaustive(file: &File) -> Option<Vec<Mime>> {
 /// assert_eq!(result, Some("image/gif"));
 /// ```
 pub fn from_filepath(path: &Path) -> Option<Mime> {
-    match File::open(path) {
-        Ok(file) => from_file(&file),
-        Err(_) => None,
-    }
+    DEFAULT_DETECTOR.from_filepath(path)
+}
+
+/// Gets the MIME type of a byte stream as a [`MediaType`].
+///
+/// # Examples
+/// ```rust
+/// // Load a GIF file
+/// let input: &[u8] = include_bytes!("../tests/inputs/image/gif/gif.gif");
+///
+/// // Find the MIME type of the GIF
+/// let result = tika_magic::from_u8_media_type(input);
+/// assert_eq!(result.type_(), "image");
+/// assert_eq!(result.subtype(), "gif");
+/// ```
+pub fn from_u8_media_type(bytes: &[u8]) -> MediaType {
+    DEFAULT_DETECTOR.from_u8_media_type(bytes)
+}
+
+/// Gets the MIME type for a path as a [`MediaType`].
+///
+/// Returns None if the file cannot be opened or if no MIME type is found.
+///
+/// # Examples
+/// ```rust
+/// use std::path::Path;
+///
+/// // Get path to a GIF file
+/// let path = Path::new("./tests/inputs/image/gif/gif.gif");
+///
+/// // Find the MIME type of the GIF
+/// let result = tika_magic::from_filepath_media_type(path).unwrap();
+/// assert_eq!(result.to_string(), "image/gif");
+/// ```
+pub fn from_filepath_media_type(path: &Path) -> Option<MediaType> {
+    DEFAULT_DETECTOR.from_filepath_media_type(path)
+}
+
+fn to_media_type(mime: Mime) -> MediaType {
+    MediaType::parse(mime).expect("detected MIME types are valid")
 }
 
 /// Gets all the MIME types that match for a path.
@@ -289,13 +467,241 @@ pub fn from_filepath(path: &Path) -> Option<Mime> {
 /// assert_eq!(result, Some(vec!["video/mp4", "video/quicktime"]));
 /// ```
 pub fn from_filepath_exhaustive(path: &Path) -> Option<Vec<Mime>> {
-    match File::open(path) {
-        Ok(file) => from_file_exhaustive(&file),
-        Err(_) => None,
+    DEFAULT_DETECTOR.from_filepath_exhaustive(path)
+}
+
+/// Gets the MIME type for a file, telling apart why detection failed.
+///
+/// Does not look at file name or extension, just the contents.
+///
+/// Fails with [`Error::Io`] if the file cannot be read, [`Error::Empty`] if it has no
+/// contents and [`Error::Unidentified`] if no type matches.
+///
+/// # Examples
+/// ```rust
+/// use std::fs::File;
+///
+/// // Get path to a GIF file
+/// let file = File::open("./tests/inputs/image/gif/gif.gif").unwrap();
+///
+/// // Find the MIME type of the GIF
+/// let result = tika_magic::try_from_file(&file).unwrap();
+/// assert_eq!(result.mime(), "image/gif");
+/// ```
+pub fn try_from_file(file: &File) -> Result<Detection, Error> {
+    DEFAULT_DETECTOR.try_from_file(file)
+}
+
+/// Gets the MIME type for a path, telling apart why detection failed.
+///
+/// See [`try_from_file`].
+///
+/// # Examples
+/// ```rust
+/// use std::path::Path;
+/// use tika_magic::Error;
+///
+/// // Get path to a GIF file
+/// let path = Path::new("./tests/inputs/image/gif/gif.gif");
+///
+/// // Find the MIME type of the GIF
+/// let result = tika_magic::try_from_filepath(path).unwrap();
+/// assert_eq!(result.mime(), "image/gif");
+///
+/// // Missing files are I/O errors
+/// let result = tika_magic::try_from_filepath(Path::new("./does/not/exist"));
+/// assert!(matches!(result, Err(Error::Io(_))));
+/// ```
+pub fn try_from_filepath(path: &Path) -> Result<Detection, Error> {
+    DEFAULT_DETECTOR.try_from_filepath(path)
+}
+
+/// Checks if a file matches the given MIME type, telling apart why the check
+/// could not be done.
+///
+/// Fails with [`Error::UnknownMimeType`] if the MIME type is not known, [`Error::Io`]
+/// if the file cannot be read and [`Error::Empty`] if it has no contents.
+///
+/// # Examples
+/// ```rust
+/// use std::fs::File;
+///
+/// // Get path to a GIF file
+/// let file = File::open("./tests/inputs/image/gif/gif.gif").unwrap();
+///
+/// // Check if the MIME and the file are a match
+/// let result = tika_magic::try_match_file("image/gif", &file);
+/// assert_eq!(result.unwrap(), true);
+/// ```
+pub fn try_match_file(mimetype: &str, file: &File) -> Result<bool, Error> {
+    DEFAULT_DETECTOR.try_match_file(mimetype, file)
+}
+
+/// Checks if the file at the given path matches the given MIME type, telling apart
+/// why the check could not be done.
+///
+/// See [`try_match_file`].
+///
+/// # Examples
+/// ```rust
+/// use std::path::Path;
+/// use tika_magic::Error;
+///
+/// // Get path to a GIF file
+/// let path = Path::new("./tests/inputs/image/gif/gif.gif");
+///
+/// // Check if the MIME and the file are a match
+/// let result = tika_magic::try_match_filepath("image/gif", path);
+/// assert_eq!(result.unwrap(), true);
+///
+/// // Types that are not in the database are errors, not mismatches
+/// let result = tika_magic::try_match_filepath("image/x-made-up", path);
+/// assert!(matches!(result, Err(Error::UnknownMimeType(_))));
+/// ```
+pub fn try_match_filepath(mimetype: &str, path: &Path) -> Result<bool, Error> {
+    DEFAULT_DETECTOR.try_match_filepath(mimetype, path)
+}
+
+/// Gets the canonical name of a MIME type or one of its aliases.
+///
+/// The name is matched ignoring case and surrounding whitespace. Parameters are kept
+/// when they are part of a known name, like `application/vnd.lotus-1-2-3;version=4`,
+/// and dropped otherwise. Use [`canonicalize_with_params`] to keep them.
+///
+/// Returns None if the type is not known.
+///
+/// # Examples
+/// ```rust
+/// assert_eq!(tika_magic::canonicalize("text/xml"), Some("application/xml"));
+/// assert_eq!(
+///     tika_magic::canonicalize("Application/X-Zip-Compressed"),
+///     Some("application/zip")
+/// );
+/// assert_eq!(
+///     tika_magic::canonicalize("text/xml; charset=utf-8"),
+///     Some("application/xml")
+/// );
+/// assert_eq!(tika_magic::canonicalize("application/x-unknown"), None);
+/// ```
+pub fn canonicalize(mimetype: &str) -> Option<Mime> {
+    registry::resolve(mimetype).map(|m| m.get_mime())
+}
+
+/// Gets the canonical name of a MIME type or one of its aliases, keeping any
+/// parameters that are not part of the name itself.
+///
+/// Parameter names are lowercased and whitespace around them is removed, the values
+/// are kept as they are.
+///
+/// Returns None if the type is not known.
+///
+/// # Examples
+/// ```rust
+/// assert_eq!(
+///     tika_magic::canonicalize_with_params("TEXT/XML; Charset=UTF-8").as_deref(),
+///     Some("application/xml;charset=UTF-8")
+/// );
+/// assert_eq!(
+///     tika_magic::canonicalize_with_params("application/vnd.lotus-1-2-3; version=4").as_deref(),
+///     Some("application/vnd.lotus-1-2-3;version=4")
+/// );
+/// ```
+pub fn canonicalize_with_params(mimetype: &str) -> Option<String> {
+    let normalized = registry::normalize(mimetype);
+    if let Some(checker) = registry::resolve(&normalized) {
+        let mime = checker.get_mime();
+        if !normalized.contains(';') || mime.contains(';') {
+            return Some(mime.to_string());
+        }
+
+        let (_, params) = normalized.split_once(';')?;
+        return Some(format!("{mime};{params}"));
     }
+
+    None
+}
+
+/// Gets the MIME type from a byte stream and the name it was stored under.
+///
+/// The contents are checked first. If the magic only finds a generic supertype, like
+/// `application/zip` or `text/plain`, and the name matches a more specific subtype,
+/// like `*.jar` or `*.csv`, the type from the name is returned. If the two disagree,
+/// the contents win.
+///
+/// # Examples
+/// ```rust
+/// // Load a plain ZIP file
+/// let input: &[u8] = include_bytes!("../tests/inputs/application/zip/zip.zip");
+///
+/// // A JAR is a ZIP, so the name refines the result
+/// let result = tika_magic::from_u8_with_name(input, "library.jar");
+/// assert_eq!(result, "application/java-archive");
+///
+/// // A PNG is not a ZIP, so the contents win
+/// let result = tika_magic::from_u8_with_name(input, "image.png");
+/// assert_eq!(result, "application/zip");
+/// ```
+pub fn from_u8_with_name(bytes: &[u8], name: &str) -> Mime {
+    DEFAULT_DETECTOR.from_u8_with_name(bytes, name)
+}
+
+/// Gets the MIME type for a path, using both its contents and its file name.
+///
+/// See [`from_u8_with_name`] for how the two are combined.
+///
+/// Returns None if the file cannot be opened or read.
+///
+/// # Examples
+/// ```rust
+/// use std::path::Path;
+///
+/// // Get path to a GIF file
+/// let path = Path::new("./tests/inputs/image/gif/gif.gif");
+///
+/// // Find the MIME type of the GIF
+/// let result = tika_magic::from_filepath_with_name(path);
+/// assert_eq!(result, Some("image/gif"));
+/// ```
+pub fn from_filepath_with_name(path: &Path) -> Option<Mime> {
+    DEFAULT_DETECTOR.from_filepath_with_name(path)
+}
+
+/// Gets the MIME type for a file name.
+///
+/// Does not look at the contents, just the name. The name is matched against the
+/// glob patterns of the Tika database: literal names such as `Makefile` first, then
+/// the longest matching extension, then any remaining wildcard patterns. If nothing
+/// matches, the lowercased name is tried. Leading directories are ignored.
+///
+/// Returns None if no glob matches the name.
+///
+/// # Examples
+/// ```rust
+/// assert_eq!(tika_magic::from_filename("photo.PNG"), Some("image/png"));
+/// assert_eq!(tika_magic::from_filename("Makefile"), Some("text/x-makefile"));
+/// assert_eq!(tika_magic::from_filename("crawl.warc.gz"), Some("application/warc+gz"));
+/// ```
+pub fn from_filename(name: &str) -> Option<Mime> {
+    magic::match_name(name).map(|m| m.get_mime())
+}
+
+/// Gets the MIME type for a file extension.
+///
+/// The extension may be given with or without its leading dot, and may contain
+/// several dots (`tar.gz`). Falls back to the lowercased extension.
+///
+/// Returns None if no type is registered for the extension.
+///
+/// # Examples
+/// ```rust
+/// assert_eq!(tika_magic::from_extension("pdf"), Some("application/pdf"));
+/// assert_eq!(tika_magic::from_extension(".JPG"), Some("image/jpeg"));
+/// ```
+pub fn from_extension(extension: &str) -> Option<Mime> {
+    magic::match_extension(extension).map(|m| m.get_mime())
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "all_types"))]
 mod tests {
     use super::*;
     use rstest::rstest;
@@ -339,4 +745,223 @@ mod tests {
         assert_eq!(from_u8(data), "text/x-uuencode");
         assert!(match_u8("text/x-uuencode", data));
     }
+
+    #[rstest]
+    #[case("index.html", Some("text/html"))]
+    #[case("README", Some("text/plain"))]
+    #[case("LICENSE", Some("text/plain"))]
+    #[case("Makefile", Some("text/x-makefile"))]
+    #[case("crawl.warc.gz", Some("application/warc+gz"))]
+    #[case("archive.gz", Some("application/gzip"))]
+    #[case("archive-gz", Some("application/gzip"))]
+    #[case("SCAN.JPG", Some("image/jpeg"))]
+    #[case("/tmp/uploads/report.pdf", Some("application/pdf"))]
+    #[case("C:\\Users\\report.pdf", Some("application/pdf"))]
+    #[case("no_extension", None)]
+    #[case("", None)]
+    fn test_from_filename(#[case] name: &str, #[case] expected: Option<Mime>) {
+        assert_eq!(from_filename(name), expected);
+    }
+
+    #[rstest]
+    #[case("png", Some("image/png"))]
+    #[case(".png", Some("image/png"))]
+    #[case("PNG", Some("image/png"))]
+    #[case("warc.gz", Some("application/warc+gz"))]
+    #[case("", None)]
+    #[case("not-a-real-extension", None)]
+    fn test_from_extension(#[case] extension: &str, #[case] expected: Option<Mime>) {
+        assert_eq!(from_extension(extension), expected);
+    }
+
+    #[rstest]
+    #[case(b"a,b,c\n1,2,3\n", "data.csv", "text/csv")]
+    #[case(b"hello world, this is text\n", "data.csv", "text/csv")]
+    #[case(b"hello world, this is text\n", "data.png", "text/plain")]
+    #[case(b"\x89PNG\x0D\x0A\x1A\x0A", "photo.jpg", "image/png")]
+    #[case(b"\xde\xad\xbe\xef", "notes.txt", "text/plain")]
+    #[case(b"\xde\xad\xbe\xef", "no_extension", "application/octet-stream")]
+    #[case(b"<?xml version=\"1.0\"?><drawing/>", "drawing.svg", "image/svg+xml")]
+    #[case(b"<?xml version=\"1.0\"?><drawing/>", "drawing.png", "application/xml")]
+    fn test_from_u8_with_name(#[case] bytes: &[u8], #[case] name: &str, #[case] expected: Mime) {
+        assert_eq!(from_u8_with_name(bytes, name), expected);
+    }
+
+    #[rstest]
+    fn test_from_filepath_with_name() {
+        let path = Path::new("./tests/inputs/text/x-matlab/testMATLAB.m");
+        assert!(path.exists());
+        assert_eq!(from_filepath_with_name(path), from_filepath(path));
+        assert_eq!(
+            from_filepath_with_name(Path::new("./does/not/exist.txt")),
+            None
+        );
+    }
+
+    #[rstest]
+    #[case("application/xml", Some("application/xml"))]
+    #[case("text/xml", Some("application/xml"))]
+    #[case("application/x-zip-compressed", Some("application/zip"))]
+    #[case("  IMAGE/PNG  ", Some("image/png"))]
+    #[case("text/html;charset=utf-8", Some("text/html"))]
+    #[case(
+        "application/vnd.ms-visio.drawing.macroenabled.12",
+        Some("application/vnd.ms-visio.drawing.macroEnabled.12")
+    )]
+    #[case(
+        "application/vnd.lotus-1-2-3; version=4",
+        Some("application/vnd.lotus-1-2-3;version=4")
+    )]
+    #[case(
+        "application/vnd.lotus-1-2-3; version=99",
+        Some("application/vnd.lotus-1-2-3")
+    )]
+    #[case("application/x-not-a-real-type", None)]
+    #[case("", None)]
+    fn test_canonicalize(#[case] mimetype: &str, #[case] expected: Option<Mime>) {
+        assert_eq!(canonicalize(mimetype), expected);
+    }
+
+    #[rstest]
+    #[case("text/xml", Some("application/xml"))]
+    #[case("text/xml;charset=utf-8", Some("application/xml;charset=utf-8"))]
+    #[case(
+        "Text/Plain ; Format=flowed ; CHARSET=\"UTF-8\"",
+        Some("text/plain;format=flowed;charset=\"UTF-8\"")
+    )]
+    #[case("application/x-not-a-real-type;charset=utf-8", None)]
+    fn test_canonicalize_with_params(#[case] mimetype: &str, #[case] expected: Option<&str>) {
+        assert_eq!(canonicalize_with_params(mimetype).as_deref(), expected);
+    }
+
+    #[rstest]
+    fn test_from_u8_media_type() {
+        let data = include_bytes!("../tests/inputs/application/vnd.openxmlformats-officedocument.wordprocessingml.document/testWORD.docx");
+        let media_type = from_u8_media_type(data);
+        assert_eq!(media_type.type_(), "application");
+        assert!(media_type
+            .subtype()
+            .starts_with("vnd.openxmlformats-officedocument"));
+        assert_eq!(media_type.params().count(), 0);
+    }
+
+    #[rstest]
+    fn test_try_from_filepath_errors() {
+        let dir = std::env::temp_dir().join(format!("tika-magic-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let empty = dir.join("empty");
+        std::fs::write(&empty, b"").unwrap();
+        assert!(matches!(try_from_filepath(&empty), Err(Error::Empty)));
+        assert!(matches!(
+            try_match_filepath("image/png", &empty),
+            Err(Error::Empty)
+        ));
+
+        let unknown = dir.join("unknown");
+        std::fs::write(&unknown, [0xfa, 0xce, 0xb0, 0x0c, 0x00, 0x17]).unwrap();
+        assert!(matches!(
+            try_from_filepath(&unknown),
+            Err(Error::Unidentified)
+        ));
+        assert!(!try_match_filepath("image/png", &unknown).unwrap());
+
+        let png = Path::new("./tests/inputs/image/png/png.png");
+        assert!(try_match_filepath("IMAGE/PNG", png).unwrap());
+        assert!(try_match_filepath("image/png; q=1", png).unwrap());
+        assert!(match_u8(
+            " Image/Png ",
+            include_bytes!("../tests/inputs/image/png/png.png")
+        ));
+
+        let missing = dir.join("missing");
+        assert!(matches!(try_from_filepath(&missing), Err(Error::Io(_))));
+        assert!(matches!(
+            try_match_filepath("image/png", &missing),
+            Err(Error::Io(_))
+        ));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[rstest]
+    fn test_try_from_filepath() {
+        let detection = try_from_filepath(Path::new("./tests/inputs/image/png/png.png")).unwrap();
+        assert_eq!(detection.mime(), "image/png");
+        assert_eq!(detection.media_type().subtype(), "png");
+        assert_eq!(detection.info().unwrap().mime(), "image/png");
+        assert!(detection.bytes_read() > 0);
+    }
+
+    /// Hands out one byte per read, like a slow network stream
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match (self.0.split_first(), buf.first_mut()) {
+                (Some((byte, rest)), Some(out)) => {
+                    *out = *byte;
+                    self.0 = rest;
+                    Ok(1)
+                }
+                _ => Ok(0),
+            }
+        }
+    }
+
+    #[rstest]
+    fn test_from_reader_short_reads() {
+        let data = include_bytes!("../tests/inputs/application/illustrator+ps/testAI_PS.ai");
+        assert_eq!(
+            from_reader(Trickle(data)),
+            Some("application/illustrator+ps")
+        );
+        assert_eq!(from_reader(Trickle(b"")), None);
+    }
+
+    #[rstest]
+    fn test_from_seekable_restores_position() {
+        let data = include_bytes!("../tests/inputs/image/png/png.png");
+        let mut reader = io::Cursor::new(data.to_vec());
+        reader.set_position(0);
+        assert_eq!(from_seekable(&mut reader), Some("image/png"));
+        assert_eq!(reader.position(), 0);
+
+        reader.set_position(data.len() as u64);
+        assert_eq!(from_seekable(&mut reader), None);
+    }
+
+    #[cfg(feature = "open_zips")]
+    #[rstest]
+    fn test_from_seekable_reads_the_zip_directory() {
+        use std::io::Write;
+        use zip::write::SimpleFileOptions;
+
+        // A document larger than the head, so its ZIP directory is past it
+        let options =
+            SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+        let mut zip = zip::ZipWriter::new(io::Cursor::new(vec![]));
+        zip.start_file("word/document.xml", options).unwrap();
+        zip.write_all(&vec![b' '; HEAD_SIZE * 2]).unwrap();
+        let data = zip.finish().unwrap().into_inner();
+
+        assert_eq!(from_u8(&data[..HEAD_SIZE]), "application/zip");
+        assert_eq!(
+            from_seekable(io::Cursor::new(&data)),
+            Some("application/vnd.openxmlformats-officedocument.wordprocessingml.document")
+        );
+    }
+
+    #[rstest]
+    fn test_match_u8_containers() {
+        let data = include_bytes!("../tests/inputs/application/vnd.openxmlformats-officedocument.wordprocessingml.document/testWORD.docx");
+        assert!(!match_u8("image/png", data));
+        assert!(!match_u8_or_subtype("image/png", data));
+        assert!(match_u8_or_subtype("application/zip", data));
+        assert!(match_u8_or_subtype("application/octet-stream", data));
+
+        let data = include_bytes!("../tests/inputs/application/msword/Doc1_ole.doc");
+        assert!(!match_u8("image/png", data));
+        assert!(match_u8_or_subtype("application/x-tika-msoffice", data));
+    }
 }
@@ -0,0 +1,105 @@
+use super::generated::{DISPATCH, DISPATCH_ANY, DISPATCH_OFFSETS, MIME_TYPES};
+use super::{MimeTypeChecker, RangeHits};
+
+/// Types of the dispatch index, each with its position among all the types
+pub(crate) type DispatchList = &'static [(u16, &'static dyn MimeTypeChecker)];
+
+/// The types of [`MIME_TYPES`] that can match the bytes, in the same order.
+///
+/// The generated dispatch index lists every type under the byte its rule, or the
+/// rule of one of its descendants, needs at a fixed offset, so only the lists for
+/// the bytes actually there are merged by the position each type has among all the
+/// types. Skipping the other types does not change which type is found first.
+pub(crate) fn plausible_types(bytes: &[u8]) -> PlausibleTypes {
+    let mut lists = [DISPATCH_ANY; DISPATCH_OFFSETS.len() + 1];
+    for ((list, table), offset) in lists.iter_mut().zip(&DISPATCH).zip(DISPATCH_OFFSETS) {
+        *list = table[bytes.get(offset).map_or(256, |byte| usize::from(*byte))];
+    }
+
+    PlausibleTypes { lists }
+}
+
+/// An iterator merging the sorted index lists that apply to some bytes, see
+/// [`plausible_types`].
+pub(crate) struct PlausibleTypes {
+    lists: [DispatchList; DISPATCH_OFFSETS.len() + 1],
+}
+
+impl Iterator for PlausibleTypes {
+    type Item = &'static dyn MimeTypeChecker;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let list = self
+            .lists
+            .iter_mut()
+            .filter(|list| !list.is_empty())
+            .min_by_key(|list| list[0].0)?;
+
+        let checker = list[0].1;
+        *list = &list[1..];
+        Some(checker)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::check_recursive;
+    use std::fs;
+    use std::path::Path;
+
+    /// The first type found by the full scan and by the dispatch index
+    fn first_match(bytes: &[u8]) -> (Option<&'static str>, Option<&'static str>) {
+        let scan = MIME_TYPES
+            .iter()
+            .find_map(|checker| check_recursive(*checker, bytes, &RangeHits::none()));
+        let hits = RangeHits::new(bytes);
+        let dispatched =
+            plausible_types(bytes).find_map(|checker| check_recursive(checker, bytes, &hits));
+        (scan, dispatched)
+    }
+
+    #[test]
+    fn test_plausible_types_are_ordered() {
+        for bytes in [&b""[..], b"PK\x03\x04", b"hello world"] {
+            let positions = plausible_types(bytes)
+                .map(|checker| {
+                    MIME_TYPES
+                        .iter()
+                        .position(|other| other.get_mime() == checker.get_mime())
+                        .unwrap()
+                })
+                .collect::<Vec<_>>();
+            assert!(positions.windows(2).all(|pair| pair[0] < pair[1]));
+            if cfg!(feature = "all_types") {
+                assert!(positions.len() < MIME_TYPES.len() / 4);
+            }
+        }
+    }
+
+    #[test]
+    fn test_dispatch_agrees_with_scan() {
+        fn visit(dir: &Path, f: &mut dyn FnMut(&[u8])) {
+            for entry in fs::read_dir(dir).unwrap() {
+                let path = entry.unwrap().path();
+                if path.is_dir() {
+                    visit(&path, f);
+                } else {
+                    let mut data = fs::read(&path).unwrap();
+                    data.truncate(0x20000);
+                    f(&data);
+                }
+            }
+        }
+
+        let inputs = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/inputs");
+        visit(&inputs, &mut |data| {
+            // Also cut short before and within the offsets the index is keyed on
+            for len in [data.len(), 0, 1, 3, 4, 5, 8, 257, 258, 262] {
+                let bytes = &data[..len.min(data.len())];
+                let (scan, dispatched) = first_match(bytes);
+                assert_eq!(scan, dispatched, "{:?}", &bytes[..bytes.len().min(16)]);
+            }
+        });
+    }
+}
@@ -0,0 +1,174 @@
+use crate::magic::{MimeTypeChecker, EXT_MAP, REGEX_GLOBS};
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// Glob patterns that are neither literal file names nor plain `*.ext` extensions,
+/// such as `i_*.txt` or `*-gz`. Longest patterns are tried first, like Tika does.
+static WILDCARD_GLOBS: Lazy<Vec<(&'static str, &'static dyn MimeTypeChecker)>> = Lazy::new(|| {
+    let mut globs = EXT_MAP
+        .entries()
+        .filter(|(pattern, _)| is_wildcard(pattern) && !is_extension(pattern))
+        .filter(|(pattern, _)| !REGEX_GLOBS.contains(pattern))
+        .filter_map(|(pattern, checkers)| Some((*pattern, *checkers.first()?)))
+        .collect::<Vec<_>>();
+
+    globs.sort_by(|(a, _), (b, _)| b.len().cmp(&a.len()).then(a.cmp(b)));
+    globs
+});
+
+/// Patterns marked `isregex`, such as `^rdf$`, which must match the whole name.
+static REGEX_PATTERNS: Lazy<Vec<(Regex, &'static dyn MimeTypeChecker)>> = Lazy::new(|| {
+    REGEX_GLOBS
+        .iter()
+        .filter_map(|pattern| {
+            let regex = Regex::new(&format!("^(?:{pattern})$")).ok()?;
+            Some((regex, lookup(pattern)?))
+        })
+        .collect()
+});
+
+fn is_wildcard(pattern: &str) -> bool {
+    pattern.contains(['*', '?'])
+}
+
+fn is_extension(pattern: &str) -> bool {
+    match pattern.strip_prefix("*.") {
+        Some(ext) => !is_wildcard(ext),
+        None => false,
+    }
+}
+
+fn lookup(pattern: &str) -> Option<&'static dyn MimeTypeChecker> {
+    EXT_MAP.get(pattern)?.first().copied()
+}
+
+/// Matches `name` against a glob pattern supporting `*` and `?`.
+fn glob_matches(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.as_bytes();
+    let name = name.as_bytes();
+
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(c) if *c == b'?' || *c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    backtrack = Some((star, matched + 1));
+                    p = star + 1;
+                    n = matched + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == b'*')
+}
+
+fn match_name_exact(name: &str) -> Option<&'static dyn MimeTypeChecker> {
+    // Literal file names such as `Makefile` or `README`
+    if !is_wildcard(name) && !REGEX_GLOBS.contains(&name) {
+        if let Some(checker) = lookup(name) {
+            return Some(checker);
+        }
+    }
+
+    // Extensions, longest first so `*.warc.gz` wins over `*.gz`
+    for (i, _) in name.match_indices('.') {
+        if let Some(checker) = lookup(&format!("*{}", &name[i..])) {
+            return Some(checker);
+        }
+    }
+
+    WILDCARD_GLOBS
+        .iter()
+        .find(|(pattern, _)| glob_matches(pattern, name))
+        .map(|(_, checker)| *checker)
+        .or_else(|| {
+            REGEX_PATTERNS
+                .iter()
+                .find(|(regex, _)| regex.is_match(name))
+                .map(|(_, checker)| *checker)
+        })
+}
+
+/// Finds the type whose glob patterns match the given file name.
+///
+/// Any leading directories are ignored. Names are matched case-sensitively first,
+/// then lowercased, following Tika's `MimeTypes.getMimeType(String)`.
+pub fn match_name(name: &str) -> Option<&'static dyn MimeTypeChecker> {
+    let name = match name.rfind(['/', '\\']) {
+        Some(i) => &name[i + 1..],
+        None => name,
+    };
+
+    if name.is_empty() {
+        return None;
+    }
+
+    if let Some(checker) = match_name_exact(name) {
+        return Some(checker);
+    }
+
+    let lowercase = name.to_lowercase();
+    if lowercase != name {
+        return match_name_exact(&lowercase);
+    }
+
+    None
+}
+
+/// Finds the type registered for the `*.<extension>` glob.
+pub fn match_extension(extension: &str) -> Option<&'static dyn MimeTypeChecker> {
+    let extension = extension.strip_prefix('.').unwrap_or(extension);
+    if extension.is_empty() {
+        return None;
+    }
+
+    let pattern = format!("*.{extension}");
+    lookup(&pattern).or_else(|| lookup(&pattern.to_lowercase()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_matches() {
+        assert!(glob_matches("i_*.txt", "i_investigation.txt"));
+        assert!(glob_matches("*-gz", "archive-gz"));
+        assert!(glob_matches("a?c", "abc"));
+        assert!(!glob_matches("i_*.txt", "investigation.txt"));
+        assert!(!glob_matches("*-gz", "archive.gz"));
+    }
+
+    #[test]
+    fn test_extension_classification() {
+        assert!(is_extension("*.tar.gz"));
+        assert!(!is_extension("*-gz"));
+        assert!(!is_extension("README"));
+        assert!(!is_wildcard("README"));
+        assert!(!is_wildcard("notes[1].txt"));
+    }
+
+    #[test]
+    #[cfg(feature = "all_types")]
+    fn test_regex_globs() {
+        assert_eq!(match_name("rdf").unwrap().get_mime(), "application/rdf+xml");
+        assert_eq!(
+            match_name("data/OWL").unwrap().get_mime(),
+            "application/rdf+xml"
+        );
+        assert!(match_name("rdfs").is_none());
+        assert!(match_name("^rdf$").is_none());
+    }
+}
@@ -4,22 +4,41 @@ use regex::bytes::Regex;
 use std::cmp::{max, min};
 use std::sync::Arc;
 
+mod dispatch;
+#[cfg_attr(feature = "rule_table", path = "generated_table.rs")]
 mod generated;
+mod glob;
 mod ole;
+mod range_hits;
+mod rule;
+#[cfg(feature = "rule_table")]
+mod table;
 mod zip;
 
+pub(crate) use self::dispatch::{plausible_types, DispatchList};
+pub use self::glob::{match_extension, match_name};
 pub use self::ole::OleSpecialHandler;
+pub(crate) use self::range_hits::{offset_range_case_insensitive_hit, offset_range_hit, RangeHits};
+pub use self::rule::Rule;
+#[cfg(feature = "rule_table")]
+use self::table::TableType;
 pub use self::zip::ZipSpecialHandler;
 pub use generated::{EXT_MAP, MIME_MAP, MIME_TYPES};
 
 use generated::*;
 
 pub static PRIORITY_MIME_TYPES: &[&'static dyn MimeTypeChecker] = &[
+    #[cfg(any(feature = "all_types", feature = "image"))]
     &T_png_image,
+    #[cfg(any(feature = "all_types", feature = "image"))]
     &T_gif_image,
+    #[cfg(any(feature = "all_types", feature = "image"))]
     &T_jpeg_image,
+    #[cfg(any(feature = "all_types", feature = "application", feature = "archives"))]
     &T_zip_application,
+    #[cfg(any(feature = "all_types", feature = "application", feature = "office"))]
     &T_pdf_application,
+    #[cfg(any(feature = "all_types", feature = "application"))]
     &T_x_dosexec_application,
 ];
 
@@ -31,10 +50,23 @@ struct MimeType {
 }
 
 pub trait MimeTypeChecker: Send + Sync {
-    fn check(&self, bytes: &[u8]) -> bool;
+    fn check(&self, bytes: &[u8]) -> bool {
+        self.check_with(bytes, &RangeHits::none())
+    }
+    /// Like `check`, with the range rules looked up in hits shared by the checks
+    /// of the same bytes
+    fn check_with(&self, bytes: &[u8], hits: &RangeHits) -> bool;
+    fn get_rule(&self) -> &'static Rule;
+    fn get_priority(&self) -> u32;
     fn get_mime(&self) -> &'static str;
     fn get_ext(&self) -> &[&'static str];
+    fn get_aliases(&self) -> &[&'static str];
+    fn get_description(&self) -> Option<&'static str>;
+    fn get_acronym(&self) -> Option<&'static str>;
+    fn get_uti(&self) -> Option<&'static str>;
+    fn get_links(&self) -> &[&'static str];
     fn get_children(&self) -> &[&'static dyn MimeTypeChecker];
+    fn get_parents(&self) -> &[&'static str];
     fn is_virtual(&self) -> bool;
 }
 
@@ -51,25 +83,41 @@ pub(crate) fn offset(bytes: &[u8], start: usize, needle: &[u8]) -> bool {
     }
 }
 
-pub(crate) fn offset_range(bytes: &[u8], start: usize, end: usize, needle: &[u8]) -> bool {
+/// The part of `bytes` a range rule looks at, which may be cut short by the end of
+/// the input
+fn range(bytes: &[u8], start: usize, end: usize) -> Option<&[u8]> {
     let end = std::cmp::min(end, bytes.len().saturating_sub(1));
     if end < start || start >= bytes.len() {
-        return false;
+        return None;
     }
 
-    if let Some(slice) = &bytes.get(start..=end) {
-        slice.windows(needle.len()).any(|window| window == needle)
-    } else {
-        false
-    }
+    bytes.get(start..=end)
+}
+
+pub(crate) fn offset_range(bytes: &[u8], start: usize, end: usize, needle: &[u8]) -> bool {
+    offset_range_at(bytes, start, end, needle).is_some()
+}
+
+pub(crate) fn offset_range_at(
+    bytes: &[u8],
+    start: usize,
+    end: usize,
+    needle: &[u8],
+) -> Option<usize> {
+    let slice = range(bytes, start, end)?;
+    slice
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|i| start + i)
 }
 
+/// Masked rules get their needle already masked: the generator masks it.
 pub(crate) fn offset_mask(bytes: &[u8], start: usize, needle: &[u8], mask: &[u8]) -> bool {
     if let Some(slice) = &bytes.get(start..) {
         slice
             .iter()
             .zip(needle.iter().zip(mask.iter()))
-            .all(|(s, (n, m))| (s & m) == (n & m))
+            .all(|(s, (n, m))| (s & m) == *n)
     } else {
         false
     }
@@ -82,29 +130,28 @@ pub(crate) fn offset_mask_range(
     needle: &[u8],
     mask: &[u8],
 ) -> bool {
-    let end = std::cmp::min(end, bytes.len().saturating_sub(1));
-    if end < start || start >= bytes.len() {
-        return false;
-    }
-
-    if let Some(slice) = &bytes.get(start..=end) {
-        let masked_needle = needle
-            .iter()
-            .zip(mask.iter())
-            .map(|(n, m)| n & m)
-            .collect::<Vec<_>>();
+    offset_mask_range_at(bytes, start, end, needle, mask).is_some()
+}
 
-        slice.windows(needle.len()).any(|window| {
+pub(crate) fn offset_mask_range_at(
+    bytes: &[u8],
+    start: usize,
+    end: usize,
+    needle: &[u8],
+    mask: &[u8],
+) -> Option<usize> {
+    let slice = range(bytes, start, end)?;
+    slice
+        .windows(needle.len())
+        .position(|window| {
             window
                 .iter()
                 .zip(mask.iter())
                 .map(|(w, m)| w & m)
-                .zip(masked_needle.iter())
-                .all(|(w, m)| w == *m)
+                .zip(needle.iter())
+                .all(|(w, n)| w == *n)
         })
-    } else {
-        false
-    }
+        .map(|i| start + i)
 }
 
 pub(crate) fn prefix_case_insensitive(bytes: &[u8], needle: &[u8]) -> bool {
@@ -134,18 +181,20 @@ pub(crate) fn offset_range_case_insensitive(
     end: usize,
     needle: &[u8],
 ) -> bool {
-    let end = std::cmp::min(end, bytes.len().saturating_sub(1));
-    if end < start || start >= bytes.len() {
-        return false;
-    }
+    offset_range_case_insensitive_at(bytes, start, end, needle).is_some()
+}
 
-    if let Some(slice) = &bytes.get(start..=end) {
-        slice
-            .windows(needle.len())
-            .any(|window| window.eq_ignore_ascii_case(needle))
-    } else {
-        false
-    }
+pub(crate) fn offset_range_case_insensitive_at(
+    bytes: &[u8],
+    start: usize,
+    end: usize,
+    needle: &[u8],
+) -> Option<usize> {
+    let slice = range(bytes, start, end)?;
+    slice
+        .windows(needle.len())
+        .position(|window| window.eq_ignore_ascii_case(needle))
+        .map(|i| start + i)
 }
 
 pub(crate) fn prefix_string(bytes: &[u8], needle: &str) -> bool {
@@ -172,14 +221,43 @@ pub(crate) fn regex(bytes: &[u8], start: usize, needle: &Regex) -> bool {
     }
 }
 
-pub(crate) fn regex_range(bytes: &[u8], start: usize, end: usize, needle: &Regex) -> bool {
-    if let Some(slice) = &bytes.get(start..=end) {
-        needle.is_match(slice)
+pub(crate) fn regex_at(bytes: &[u8], start: usize, needle: &Regex) -> Option<usize> {
+    let slice = bytes.get(start..)?;
+    needle.find(slice).map(|m| start + m.start())
+}
+
+/// The part of `bytes` a regex or unicode-LE range rule looks at. Unless the bytes
+/// are the whole input, the range must fit in them, as the rest of it may still
+/// come.
+fn whole_range(bytes: &[u8], start: usize, end: usize, whole: bool) -> Option<&[u8]> {
+    if whole {
+        range(bytes, start, end)
     } else {
-        false
+        bytes.get(start..=end)
     }
 }
 
+pub(crate) fn regex_range(
+    bytes: &[u8],
+    hits: &RangeHits,
+    start: usize,
+    end: usize,
+    needle: &Regex,
+) -> bool {
+    regex_range_at(bytes, start, end, needle, hits.is_whole()).is_some()
+}
+
+pub(crate) fn regex_range_at(
+    bytes: &[u8],
+    start: usize,
+    end: usize,
+    needle: &Regex,
+    whole: bool,
+) -> Option<usize> {
+    let slice = whole_range(bytes, start, end, whole)?;
+    needle.find(slice).map(|m| start + m.start())
+}
+
 pub(crate) fn little32(bytes: &[u8], start: usize, needle: u32) -> bool {
     if let Some(slice) = bytes.get(start..) {
         if slice.len() >= 4 {
@@ -219,12 +297,25 @@ pub(crate) fn unicode_le(bytes: &[u8], start: usize, needle: &[u8]) -> bool {
     }
 }
 
-pub(crate) fn unicode_le_range(bytes: &[u8], start: usize, end: usize, needle: &[u8]) -> bool {
-    if let Some(slice) = bytes.get(start..=end) {
-        offset_range(bytes, start, end, needle)
-    } else {
-        false
-    }
+pub(crate) fn unicode_le_range(
+    bytes: &[u8],
+    hits: &RangeHits,
+    start: usize,
+    end: usize,
+    needle: &[u8],
+) -> bool {
+    unicode_le_range_at(bytes, start, end, needle, hits.is_whole()).is_some()
+}
+
+pub(crate) fn unicode_le_range_at(
+    bytes: &[u8],
+    start: usize,
+    end: usize,
+    needle: &[u8],
+    whole: bool,
+) -> Option<usize> {
+    whole_range(bytes, start, end, whole)?;
+    offset_range_at(bytes, start, end, needle)
 }
 
 pub fn file_is_text(bytes: &[u8]) -> bool {
@@ -253,30 +344,63 @@ pub fn file_is_text(bytes: &[u8]) -> bool {
     }
 }
 
-pub(crate) fn rootxml(bytes: &[u8], local_name: &str, namespace_uri: &str) -> bool {
-    let local = rootxml_local(bytes, local_name);
+/// Where the `root-XML` helpers stop looking for the root element
+pub(crate) const ROOT_XML_END: usize = 2048;
+
+/// The ways the root element with a local name can start: `<name `, `name:name `
+/// and `:name xmlns`. The generator precomputes them for the built-in types.
+pub(crate) fn rootxml_local_needles(local_name: &str) -> [Vec<u8>; 3] {
+    [
+        format!("<{local_name} "),
+        format!("{local_name}:{local_name} "),
+        format!(":{local_name} xmlns"),
+    ]
+    .map(String::into_bytes)
+}
+
+pub(crate) fn rootxml(bytes: &[u8], local_needles: &[&[u8]], namespace_uri: &str) -> bool {
+    let local = rootxml_local(bytes, local_needles);
     let ns = rootxml_namespace(bytes, namespace_uri);
 
     local && ns
 }
 
-pub(crate) fn rootxml_local(bytes: &[u8], local_name: &str) -> bool {
-    let local_name_tag = format!("<{local_name} ");
-    let local_name_bytes = local_name_tag.as_bytes();
-    let local_name_with_namespace = format!("{local_name}:{local_name} ");
-    let local_name_with_namespace_bytes = local_name_with_namespace.as_bytes();
+/// Where the root element was found, if it has the given name and namespace
+pub(crate) fn rootxml_at(
+    bytes: &[u8],
+    local_needles: &[&[u8]],
+    namespace_uri: &str,
+) -> Option<usize> {
+    let local = rootxml_local_at(bytes, local_needles)?;
+    rootxml_namespace(bytes, namespace_uri).then_some(local)
+}
 
-    let localname_followed_by_namspace = format!(":{local_name} xmlns");
-    let localname_followed_by_namspace_bytes = localname_followed_by_namspace.as_bytes();
+pub(crate) fn rootxml_local(bytes: &[u8], local_needles: &[&[u8]]) -> bool {
+    rootxml_local_at(bytes, local_needles).is_some()
+}
+
+/// Where the root element was found, if it starts like one of the
+/// [`rootxml_local_needles`] of its name
+pub(crate) fn rootxml_local_at(bytes: &[u8], local_needles: &[&[u8]]) -> Option<usize> {
+    if !file_is_text(bytes) {
+        return None;
+    }
 
-    file_is_text(bytes)
-        && (offset_range_case_insensitive(bytes, 0, 2048, local_name_bytes)
-            || offset_range_case_insensitive(bytes, 0, 2048, local_name_with_namespace_bytes)
-            || offset_range_case_insensitive(bytes, 0, 2048, localname_followed_by_namspace_bytes))
+    local_needles
+        .iter()
+        .find_map(|needle| offset_range_case_insensitive_at(bytes, 0, ROOT_XML_END, needle))
 }
 
 pub(crate) fn rootxml_namespace(bytes: &[u8], namespace_uri: &str) -> bool {
-    file_is_text(bytes) && offset_range_case_insensitive(bytes, 0, 2048, namespace_uri.as_bytes())
+    rootxml_namespace_at(bytes, namespace_uri).is_some()
+}
+
+pub(crate) fn rootxml_namespace_at(bytes: &[u8], namespace_uri: &str) -> Option<usize> {
+    if !file_is_text(bytes) {
+        return None;
+    }
+
+    offset_range_case_insensitive_at(bytes, 0, ROOT_XML_END, namespace_uri.as_bytes())
 }
 
 #[cfg(test)]
@@ -288,6 +412,37 @@ mod tests {
         assert!(little32(b"\xFD\x2F\xB5\x28", 0, 0x28B52FFD));
     }
 
+    #[test]
+    fn test_ranges_cut_short() {
+        let needle = Regex::new("ab").unwrap();
+        let head = RangeHits::none();
+        let whole = RangeHits::none().whole(true);
+        assert!(regex_range(b"xxabxx", &head, 0, 5, &needle));
+        assert!(!regex_range(b"xxab", &head, 0, 5, &needle));
+        assert!(regex_range(b"xxab", &whole, 0, 5, &needle));
+        assert!(!regex_range(b"xxa", &whole, 0, 5, &needle));
+        assert!(!regex_range(b"xx", &whole, 2, 5, &needle));
+        assert_eq!(regex_range_at(b"xxab", 0, 5, &needle, true), Some(2));
+        assert!(!unicode_le_range(b"a\0b\0", &head, 0, 9, b"a\0b\0"));
+        assert!(unicode_le_range(b"a\0b\0", &whole, 0, 9, b"a\0b\0"));
+        assert!(offset_range(b"xxab", 0, 5, b"ab"));
+    }
+
+    #[test]
+    #[cfg(feature = "all_types")]
+    fn test_short_postscript_head() {
+        // Too short for the Illustrator regex over the first 8 KiB and the MATLAB one
+        // over the first 100 bytes
+        let data = include_bytes!("../../tests/inputs/application/illustrator+ps/testAI_PS.ai");
+        assert_eq!(crate::from_u8(&data[..16]), "application/postscript");
+
+        // Read to its end, the whole file is all the Illustrator regex gets to see
+        assert_eq!(
+            crate::from_reader(&data[..]),
+            Some("application/illustrator+ps")
+        );
+    }
+
     #[test]
     fn test_file_is_text() {
         assert!(file_is_text(b"Hello, world!"));
@@ -1,4 +1,30 @@
 pub struct OleSpecialHandler;
+
+impl OleSpecialHandler {
+    /// The magic the handler looks for
+    pub(crate) const MAGIC: &'static [u8] = &[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
+
+    /// Every type the handler can find
+    pub(crate) const TYPES: &'static [&'static str] = &[
+        "application/msword",
+        "application/vnd.ms-excel",
+        "application/vnd.ms-powerpoint",
+        "application/vnd.visio",
+        "application/x-tika-ooxml-protected",
+        "application/sldworks",
+        "application/vnd.stardivision.calc",
+        "application/vnd.stardivision.writer",
+        "application/vnd.stardivision.draw",
+        "application/x-mspublisher",
+        "application/x-hwp-v5",
+        "application/x-quattro-pro",
+        "application/vnd.ms-outlook",
+        "application/vnd.ms-project",
+        "application/vnd.ms-works",
+        "application/x-corelpresentations",
+    ];
+}
+
 #[cfg(feature = "open_ole")]
 mod ole_impl {
     use crate::magic::OleSpecialHandler;
@@ -7,12 +33,16 @@ mod ole_impl {
 
     impl OleSpecialHandler {
         pub(crate) fn check(&self, bytes: &[u8]) -> Option<&'static str> {
-            let magic = [0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
-            if !bytes.starts_with(&magic) {
+            self.check_stream(bytes, bytes)
+        }
+
+        /// Checks a whole stream, `head` being the bytes at its start
+        pub(crate) fn check_stream<R: Read>(&self, head: &[u8], stream: R) -> Option<&'static str> {
+            if !head.starts_with(Self::MAGIC) {
                 return None;
             }
 
-            let Ok(reader) = Reader::new(bytes) else {
+            let Ok(reader) = Reader::new(stream) else {
                 return None;
             };
 
@@ -0,0 +1,214 @@
+use super::generated::{RANGE_HEAD_LEN, RANGE_NEEDLES, RANGE_NEEDLES_CASE_INSENSITIVE};
+use super::{offset_range, offset_range_case_insensitive};
+use aho_corasick::{AhoCorasick, AhoCorasickKind};
+use once_cell::sync::Lazy;
+use std::cell::RefCell;
+
+static AUTOMATON: Lazy<AhoCorasick> = Lazy::new(|| automaton(RANGE_NEEDLES, false));
+
+static AUTOMATON_CASE_INSENSITIVE: Lazy<AhoCorasick> =
+    Lazy::new(|| automaton(RANGE_NEEDLES_CASE_INSENSITIVE, true));
+
+fn automaton(needles: &[&[u8]], case_insensitive: bool) -> AhoCorasick {
+    AhoCorasick::builder()
+        .kind(Some(AhoCorasickKind::DFA))
+        .ascii_case_insensitive(case_insensitive)
+        .build(needles)
+        .expect("range needles build an automaton")
+}
+
+/// The first occurrence of each needle of an automaton in the part of the bytes
+/// scanned so far
+struct Scan<const N: usize> {
+    scanned: usize,
+    first: [Option<usize>; N],
+}
+
+impl<const N: usize> Scan<N> {
+    fn new() -> Self {
+        Scan {
+            scanned: 0,
+            first: [None; N],
+        }
+    }
+
+    /// Where a needle first occurs, scanning as far as `end` if that was not done
+    /// yet
+    fn first(
+        &mut self,
+        automaton: &AhoCorasick,
+        bytes: &[u8],
+        needle: usize,
+        end: usize,
+    ) -> Option<usize> {
+        let target = bytes.len().min(end.saturating_add(1));
+        if self.scanned < target {
+            // Most rules look at the first few KB, a few at the first 64 KB
+            let target = target
+                .max(self.scanned * 2)
+                .min(bytes.len())
+                .min(RANGE_HEAD_LEN);
+            // Start over early enough to find the needles cut by the end of the scan
+            let resume = self
+                .scanned
+                .saturating_sub(automaton.max_pattern_len().saturating_sub(1));
+            for hit in automaton.find_overlapping_iter(&bytes[resume..target]) {
+                self.first[hit.pattern().as_usize()].get_or_insert(resume + hit.start());
+            }
+            self.scanned = target;
+        }
+
+        self.first[needle]
+    }
+}
+
+/// Where each needle of the generated range rules first occurs in some bytes.
+///
+/// The needles are all looked for in a single pass of an automaton, going only as
+/// far into the bytes as the range rules checked so far look, so the range rules
+/// of every type checked against the same bytes share it rather than each
+/// scanning their range again.
+pub(crate) struct RangeHits<'a> {
+    bytes: Option<&'a [u8]>,
+    whole: bool,
+    needles: RefCell<Scan<{ RANGE_NEEDLES.len() }>>,
+    case_insensitive_needles: RefCell<Scan<{ RANGE_NEEDLES_CASE_INSENSITIVE.len() }>>,
+}
+
+impl<'a> RangeHits<'a> {
+    /// Hits to be found in `bytes`.
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        RangeHits {
+            bytes: Some(bytes),
+            whole: false,
+            needles: RefCell::new(Scan::new()),
+            case_insensitive_needles: RefCell::new(Scan::new()),
+        }
+    }
+
+    /// No hits, each range rule scans its range on its own. Cheaper for a single
+    /// check.
+    pub(crate) fn none() -> Self {
+        RangeHits {
+            bytes: None,
+            ..RangeHits::new(&[])
+        }
+    }
+
+    /// Marks the bytes as the whole input rather than its head, as for a stream that
+    /// ended before the head was full. A regex or unicode-LE range cut short by
+    /// their end then matches what there is of it.
+    pub(crate) fn whole(mut self, whole: bool) -> Self {
+        self.whole = whole;
+        self
+    }
+
+    /// Whether the bytes are the whole input, see [`whole`](Self::whole)
+    pub(crate) fn is_whole(&self) -> bool {
+        self.whole
+    }
+
+    /// The bytes the hits are found in, if they are `bytes`
+    fn of(&self, bytes: &[u8]) -> Option<&[u8]> {
+        self.bytes.filter(|own| std::ptr::eq(*own, bytes))
+    }
+}
+
+/// Whether a needle that first occurs at `first` occurs between `start` and `end`,
+/// or None if that takes a scan of the range
+fn hit_within(first: Option<usize>, start: usize, end: usize, len: usize) -> Option<bool> {
+    match first {
+        None => Some(false),
+        Some(first) if first >= start => Some(first + len <= end.saturating_add(1)),
+        // It may occur again within the range
+        Some(_) => None,
+    }
+}
+
+/// Like [`offset_range`], for a needle of [`RANGE_NEEDLES`].
+pub(crate) fn offset_range_hit(
+    bytes: &[u8],
+    hits: &RangeHits,
+    start: usize,
+    end: usize,
+    needle: usize,
+) -> bool {
+    let needle_bytes = RANGE_NEEDLES[needle];
+    hits.of(bytes)
+        .and_then(|bytes| {
+            let first = hits
+                .needles
+                .borrow_mut()
+                .first(&AUTOMATON, bytes, needle, end);
+            hit_within(first, start, end, needle_bytes.len())
+        })
+        .unwrap_or_else(|| offset_range(bytes, start, end, needle_bytes))
+}
+
+/// Like [`offset_range_case_insensitive`], for a needle of
+/// [`RANGE_NEEDLES_CASE_INSENSITIVE`].
+pub(crate) fn offset_range_case_insensitive_hit(
+    bytes: &[u8],
+    hits: &RangeHits,
+    start: usize,
+    end: usize,
+    needle: usize,
+) -> bool {
+    let needle_bytes = RANGE_NEEDLES_CASE_INSENSITIVE[needle];
+    hits.of(bytes)
+        .and_then(|bytes| {
+            let first = hits.case_insensitive_needles.borrow_mut().first(
+                &AUTOMATON_CASE_INSENSITIVE,
+                bytes,
+                needle,
+                end,
+            );
+            hit_within(first, start, end, needle_bytes.len())
+        })
+        .unwrap_or_else(|| offset_range_case_insensitive(bytes, start, end, needle_bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hits_agree_with_scans() {
+        let inputs: &[&[u8]] = &[
+            b"",
+            b"<?xml version=\"1.0\"?>\n<tmx version=\"1.4\">",
+            include_bytes!("../../tests/inputs/image/svg+xml/svg.svg"),
+            include_bytes!("../../tests/inputs/application/pdf/testPDF_AdobeIllustrator.pdf"),
+        ];
+        for bytes in inputs {
+            let hits = RangeHits::new(bytes);
+            // Ranges that end at and past where the scan stops, and start past the
+            // first occurrence
+            for (start, end) in [(0, 8), (0, 1023), (5, 4095), (700, 65535), (0, 65535)] {
+                for (needle, needle_bytes) in RANGE_NEEDLES.iter().enumerate() {
+                    assert_eq!(
+                        offset_range_hit(bytes, &hits, start, end, needle),
+                        offset_range(bytes, start, end, needle_bytes),
+                        "{needle_bytes:?} {start}:{end}"
+                    );
+                }
+                for (needle, needle_bytes) in RANGE_NEEDLES_CASE_INSENSITIVE.iter().enumerate() {
+                    assert_eq!(
+                        offset_range_case_insensitive_hit(bytes, &hits, start, end, needle),
+                        offset_range_case_insensitive(bytes, start, end, needle_bytes),
+                        "{needle_bytes:?} {start}:{end}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_other_bytes_are_scanned() {
+        let bytes = b"<?xml version=\"1.0\"?>\n<tmx version=\"1.4\">";
+        let hits = RangeHits::new(b"");
+        let needle = RANGE_NEEDLES.iter().position(|n| *n == b"<tmx").unwrap();
+        assert!(offset_range_hit(bytes, &hits, 0, 256, needle));
+        assert!(offset_range_hit(bytes, &RangeHits::none(), 0, 256, needle));
+    }
+}
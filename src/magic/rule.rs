@@ -0,0 +1,419 @@
+use crate::magic::*;
+use once_cell::sync::Lazy;
+use regex::bytes::Regex;
+use std::fmt;
+
+/// A magic rule from the Tika database, kept as data next to the generated
+/// `check()` so a match can be explained.
+///
+/// Each primitive mirrors the helper function `check()` calls for it, offsets and
+/// ranges are inclusive like in the XML.
+#[derive(Debug)]
+pub enum Rule {
+    Offset(usize, &'static [u8]),
+    /// The range, the needle and the index of the needle in the range needles
+    OffsetRange(usize, usize, &'static [u8], usize),
+    OffsetCaseInsensitive(usize, &'static [u8]),
+    /// Like `OffsetRange`, with the index in the case insensitive range needles
+    OffsetRangeCaseInsensitive(usize, usize, &'static [u8], usize),
+    OffsetMask(usize, &'static [u8], &'static [u8]),
+    OffsetMaskRange(usize, usize, &'static [u8], &'static [u8]),
+    Regex(usize, &'static Lazy<Regex>),
+    RegexRange(usize, usize, &'static Lazy<Regex>),
+    UnicodeLe(usize, &'static [u8]),
+    UnicodeLeRange(usize, usize, &'static [u8]),
+    /// The local name, the namespace and the needles the local name is found by
+    RootXml(
+        Option<&'static str>,
+        Option<&'static str>,
+        &'static [&'static [u8]],
+    ),
+    And(&'static [Rule]),
+    Or(&'static [Rule]),
+    Never,
+}
+
+impl Rule {
+    /// Checks the rule, recording every primitive that took part in the match
+    /// and the offset it matched at.
+    ///
+    /// The primitives of a failed match are not recorded. `whole` tells whether
+    /// the bytes are the whole input, see [`RangeHits::whole`].
+    pub(crate) fn explain(
+        &'static self,
+        bytes: &[u8],
+        whole: bool,
+        hits: &mut Vec<(&'static Rule, usize)>,
+    ) -> bool {
+        let start = hits.len();
+        let matches = match self {
+            Rule::And(rules) => rules.iter().all(|rule| rule.explain(bytes, whole, hits)),
+            Rule::Or(rules) => rules.iter().any(|rule| rule.explain(bytes, whole, hits)),
+            _ => match self.find(bytes, whole) {
+                Some(offset) => {
+                    hits.push((self, offset));
+                    true
+                }
+                None => false,
+            },
+        };
+
+        if !matches {
+            hits.truncate(start);
+        }
+
+        matches
+    }
+
+    /// Checks the rule, like the generated `check()` does.
+    pub(crate) fn matches(&self, bytes: &[u8]) -> bool {
+        match self {
+            Rule::And(rules) => rules.iter().all(|rule| rule.matches(bytes)),
+            Rule::Or(rules) => rules.iter().any(|rule| rule.matches(bytes)),
+            _ => self.find(bytes, false).is_some(),
+        }
+    }
+
+    /// Checks the rule with the helpers the generated `check_with()` calls for it,
+    /// which is how the rule table checks a type.
+    pub(crate) fn matches_with(&self, bytes: &[u8], hits: &RangeHits) -> bool {
+        match self {
+            Rule::Offset(start, needle) => offset(bytes, *start, needle),
+            Rule::OffsetRange(start, end, _, needle) => {
+                offset_range_hit(bytes, hits, *start, *end, *needle)
+            }
+            Rule::OffsetCaseInsensitive(start, needle) => {
+                offset_case_insensitive(bytes, *start, needle)
+            }
+            Rule::OffsetRangeCaseInsensitive(start, end, _, needle) => {
+                offset_range_case_insensitive_hit(bytes, hits, *start, *end, *needle)
+            }
+            Rule::OffsetMask(start, needle, mask) => offset_mask(bytes, *start, needle, mask),
+            Rule::OffsetMaskRange(start, end, needle, mask) => {
+                offset_mask_range(bytes, *start, *end, needle, mask)
+            }
+            Rule::Regex(start, needle) => regex(bytes, *start, needle),
+            Rule::RegexRange(start, end, needle) => regex_range(bytes, hits, *start, *end, needle),
+            Rule::UnicodeLe(start, needle) => unicode_le(bytes, *start, needle),
+            Rule::UnicodeLeRange(start, end, needle) => {
+                unicode_le_range(bytes, hits, *start, *end, needle)
+            }
+            Rule::RootXml(Some(_), Some(namespace_uri), local_needles) => {
+                rootxml(bytes, local_needles, namespace_uri)
+            }
+            Rule::RootXml(Some(_), None, local_needles) => rootxml_local(bytes, local_needles),
+            Rule::RootXml(None, Some(namespace_uri), _) => rootxml_namespace(bytes, namespace_uri),
+            Rule::RootXml(None, None, _) | Rule::Never => false,
+            Rule::And(rules) => rules.iter().all(|rule| rule.matches_with(bytes, hits)),
+            Rule::Or(rules) => rules.iter().any(|rule| rule.matches_with(bytes, hits)),
+        }
+    }
+
+    /// Checks the rule through its `root-XML` primitives only: a match that only
+    /// takes plain magic does not count.
+    pub(crate) fn matches_root_xml(&self, bytes: &[u8]) -> bool {
+        match self {
+            Rule::And(rules) => {
+                rules.iter().any(|rule| rule.matches_root_xml(bytes))
+                    && rules.iter().all(|rule| rule.matches(bytes))
+            }
+            Rule::Or(rules) => rules.iter().any(|rule| rule.matches_root_xml(bytes)),
+            Rule::RootXml(..) => self.find(bytes, false).is_some(),
+            _ => false,
+        }
+    }
+
+    /// Whether the rule has a `root-XML` primitive.
+    pub(crate) fn has_root_xml(&self) -> bool {
+        match self {
+            Rule::And(rules) | Rule::Or(rules) => rules.iter().any(Rule::has_root_xml),
+            Rule::RootXml(..) => true,
+            _ => false,
+        }
+    }
+
+    /// How many bytes from the start the rule can look at, None if it has a regex
+    /// that looks at everything after its offset.
+    #[cfg(feature = "tokio")]
+    pub(crate) fn reach(&self) -> Option<usize> {
+        match self {
+            Rule::Offset(start, needle)
+            | Rule::OffsetCaseInsensitive(start, needle)
+            | Rule::OffsetMask(start, needle, _)
+            | Rule::UnicodeLe(start, needle) => Some(start + needle.len()),
+            Rule::OffsetRange(_, end, needle, _)
+            | Rule::OffsetRangeCaseInsensitive(_, end, needle, _)
+            | Rule::OffsetMaskRange(_, end, needle, _)
+            | Rule::UnicodeLeRange(_, end, needle) => Some(end + needle.len()),
+            Rule::Regex(start, needle) => {
+                REGEX_REACH.get(needle.as_str()).map(|reach| start + reach)
+            }
+            Rule::RegexRange(_, end, _) => Some(end + 1),
+            Rule::RootXml(_, namespace_uri, local_needles) => {
+                let longest = local_needles
+                    .iter()
+                    .map(|needle| needle.len())
+                    .chain(namespace_uri.map(str::len))
+                    .max()
+                    .unwrap_or(0);
+                Some(ROOT_XML_END + longest)
+            }
+            Rule::And(rules) | Rule::Or(rules) => rules
+                .iter()
+                .try_fold(0, |reach, rule| Some(reach.max(rule.reach()?))),
+            Rule::Never => Some(0),
+        }
+    }
+
+    /// Whether the rule can no longer match the input the bytes are the start of,
+    /// whatever comes after them. More bytes never undo a match.
+    #[cfg(feature = "tokio")]
+    pub(crate) fn never_matches(&self, bytes: &[u8], hits: &RangeHits) -> bool {
+        match self {
+            Rule::And(rules) => rules.iter().any(|rule| rule.never_matches(bytes, hits)),
+            Rule::Or(rules) => rules.iter().all(|rule| rule.never_matches(bytes, hits)),
+            _ => {
+                self.reach().is_some_and(|reach| reach <= bytes.len())
+                    && !self.matches_with(bytes, hits)
+            }
+        }
+    }
+
+    /// Finds where a primitive matches.
+    fn find(&self, bytes: &[u8], whole: bool) -> Option<usize> {
+        match self {
+            Rule::Offset(start, needle) => offset(bytes, *start, needle).then_some(*start),
+            Rule::OffsetRange(start, end, needle, _) => {
+                offset_range_at(bytes, *start, *end, needle)
+            }
+            Rule::OffsetCaseInsensitive(start, needle) => {
+                offset_case_insensitive(bytes, *start, needle).then_some(*start)
+            }
+            Rule::OffsetRangeCaseInsensitive(start, end, needle, _) => {
+                offset_range_case_insensitive_at(bytes, *start, *end, needle)
+            }
+            Rule::OffsetMask(start, needle, mask) => {
+                offset_mask(bytes, *start, needle, mask).then_some(*start)
+            }
+            Rule::OffsetMaskRange(start, end, needle, mask) => {
+                offset_mask_range_at(bytes, *start, *end, needle, mask)
+            }
+            Rule::Regex(start, needle) => regex_at(bytes, *start, needle),
+            Rule::RegexRange(start, end, needle) => {
+                regex_range_at(bytes, *start, *end, needle, whole)
+            }
+            Rule::UnicodeLe(start, needle) => unicode_le(bytes, *start, needle).then_some(*start),
+            Rule::UnicodeLeRange(start, end, needle) => {
+                unicode_le_range_at(bytes, *start, *end, needle, whole)
+            }
+            Rule::RootXml(Some(_), Some(namespace_uri), local_needles) => {
+                rootxml_at(bytes, local_needles, namespace_uri)
+            }
+            Rule::RootXml(Some(_), None, local_needles) => rootxml_local_at(bytes, local_needles),
+            Rule::RootXml(None, Some(namespace_uri), _) => {
+                rootxml_namespace_at(bytes, namespace_uri)
+            }
+            Rule::RootXml(None, None, _) | Rule::And(_) | Rule::Or(_) | Rule::Never => None,
+        }
+    }
+}
+
+fn join(f: &mut fmt::Formatter<'_>, rules: &[Rule], separator: &str) -> fmt::Result {
+    f.write_str("(")?;
+    for (i, rule) in rules.iter().enumerate() {
+        if i > 0 {
+            f.write_str(separator)?;
+        }
+        write!(f, "{rule}")?;
+    }
+    f.write_str(")")
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rule::Offset(start, needle) => {
+                write!(f, "offset {start} == \"{}\"", needle.escape_ascii())
+            }
+            Rule::OffsetRange(start, end, needle, _) => {
+                write!(
+                    f,
+                    "offset {start}:{end} contains \"{}\"",
+                    needle.escape_ascii()
+                )
+            }
+            Rule::OffsetCaseInsensitive(start, needle) => write!(
+                f,
+                "offset {start} == \"{}\" ignoring case",
+                needle.escape_ascii()
+            ),
+            Rule::OffsetRangeCaseInsensitive(start, end, needle, _) => write!(
+                f,
+                "offset {start}:{end} contains \"{}\" ignoring case",
+                needle.escape_ascii()
+            ),
+            Rule::OffsetMask(start, needle, mask) => write!(
+                f,
+                "offset {start} == \"{}\" with mask \"{}\"",
+                needle.escape_ascii(),
+                mask.escape_ascii()
+            ),
+            Rule::OffsetMaskRange(start, end, needle, mask) => write!(
+                f,
+                "offset {start}:{end} contains \"{}\" with mask \"{}\"",
+                needle.escape_ascii(),
+                mask.escape_ascii()
+            ),
+            Rule::Regex(start, needle) => {
+                write!(f, "offset {start} matches regex \"{}\"", needle.as_str())
+            }
+            Rule::RegexRange(start, end, needle) => {
+                write!(
+                    f,
+                    "offset {start}:{end} matches regex \"{}\"",
+                    needle.as_str()
+                )
+            }
+            Rule::UnicodeLe(start, needle) => {
+                write!(
+                    f,
+                    "offset {start} == unicodeLE \"{}\"",
+                    needle.escape_ascii()
+                )
+            }
+            Rule::UnicodeLeRange(start, end, needle) => write!(
+                f,
+                "offset {start}:{end} contains unicodeLE \"{}\"",
+                needle.escape_ascii()
+            ),
+            Rule::RootXml(Some(local_name), Some(namespace_uri), _) => {
+                write!(
+                    f,
+                    "root XML element <{local_name} xmlns=\"{namespace_uri}\">"
+                )
+            }
+            Rule::RootXml(Some(local_name), None, _) => {
+                write!(f, "root XML element <{local_name}>")
+            }
+            Rule::RootXml(None, Some(namespace_uri), _) => {
+                write!(f, "root XML namespace \"{namespace_uri}\"")
+            }
+            Rule::RootXml(None, None, _) | Rule::Never => f.write_str("never"),
+            Rule::And(rules) => join(f, rules, " && "),
+            Rule::Or(rules) => join(f, rules, " || "),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::Path;
+
+    static ZIP: Rule = Rule::Or(&[
+        Rule::Offset(0, b"PK\x03\x04"),
+        Rule::Offset(0, b"PK\x05\x06"),
+    ]);
+
+    static TMX: Rule = Rule::And(&[
+        Rule::Offset(0, b"<?xml"),
+        Rule::OffsetRange(0, 256, b"<tmx", 0),
+    ]);
+
+    #[test]
+    fn test_explain() {
+        let mut hits = vec![];
+        assert!(ZIP.explain(b"PK\x05\x06", false, &mut hits));
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].0.to_string(), "offset 0 == \"PK\\x05\\x06\"");
+        assert_eq!(hits[0].1, 0);
+
+        let mut hits = vec![];
+        assert!(TMX.explain(b"<?xml version=\"1.0\"?>\n<tmx>", false, &mut hits));
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[1].0.to_string(), "offset 0:256 contains \"<tmx\"");
+        assert_eq!(hits[1].1, 22);
+
+        let mut hits = vec![];
+        assert!(!TMX.explain(b"<?xml version=\"1.0\"?>\n<html>", false, &mut hits));
+        assert!(hits.is_empty());
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn test_never_matches() {
+        let hits = RangeHits::none();
+        assert!(!ZIP.never_matches(b"P", &hits));
+        assert!(!ZIP.never_matches(b"PK\x05\x06", &hits));
+        assert!(ZIP.never_matches(b"PK\x01\x02", &hits));
+        assert!(TMX.never_matches(b"<html", &hits));
+        assert!(!TMX.never_matches(b"<?xml version=\"1.0\"?>", &hits));
+        assert_eq!(TMX.reach(), Some(260));
+    }
+
+    #[test]
+    fn test_matches_root_xml() {
+        static SVG: Rule = Rule::Or(&[
+            Rule::RootXml(
+                Some("svg"),
+                Some("http://www.w3.org/2000/svg"),
+                &[b"<svg ", b"svg:svg ", b":svg xmlns"],
+            ),
+            Rule::OffsetRange(0, 256, b"<svg", 0),
+        ]);
+
+        let svg = b"<?xml version=\"1.0\"?><svg xmlns=\"http://www.w3.org/2000/svg\"/>";
+        assert!(SVG.matches_root_xml(svg));
+        assert!(SVG.matches(b"<svg>"));
+        assert!(!SVG.matches_root_xml(b"<svg>"));
+        assert!(!TMX.matches_root_xml(b"<?xml version=\"1.0\"?>\n<tmx>"));
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            ZIP.to_string(),
+            "(offset 0 == \"PK\\x03\\x04\" || offset 0 == \"PK\\x05\\x06\")"
+        );
+        assert_eq!(
+            Rule::RootXml(Some("svg"), Some("http://www.w3.org/2000/svg"), &[]).to_string(),
+            "root XML element <svg xmlns=\"http://www.w3.org/2000/svg\">"
+        );
+    }
+
+    fn visit(dir: &Path, f: &mut dyn FnMut(&[u8])) {
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                visit(&path, f);
+            } else {
+                let mut data = fs::read(&path).unwrap();
+                data.truncate(0x20000);
+                f(&data);
+            }
+        }
+    }
+
+    #[test]
+    fn test_rules_agree_with_checks() {
+        let inputs = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/inputs");
+        visit(&inputs, &mut |data| {
+            let hits = RangeHits::new(data);
+            for checker in MIME_TYPES {
+                assert_eq!(
+                    checker.get_rule().matches(data),
+                    checker.check(data),
+                    "{}",
+                    checker.get_mime()
+                );
+                assert_eq!(
+                    checker.get_rule().matches_with(data, &hits),
+                    checker.check_with(data, &hits),
+                    "{}",
+                    checker.get_mime()
+                );
+            }
+        });
+    }
+}
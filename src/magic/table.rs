@@ -0,0 +1,62 @@
+use crate::magic::{MimeTypeChecker, RangeHits, Rule};
+
+/// A type of the rule table, the generated output that keeps each type as data.
+///
+/// Rather than compiling a `check_with()` per type, the table checks a type by
+/// interpreting its [`Rule`], so the same data serves detection and explanations.
+pub(crate) struct TableType {
+    pub(super) mime: &'static str,
+    pub(super) ext: &'static [&'static str],
+    pub(super) rule: &'static Rule,
+    pub(super) priority: u32,
+    pub(super) aliases: &'static [&'static str],
+    pub(super) description: Option<&'static str>,
+    pub(super) acronym: Option<&'static str>,
+    pub(super) uti: Option<&'static str>,
+    pub(super) links: &'static [&'static str],
+    pub(super) children: &'static [&'static dyn MimeTypeChecker],
+    pub(super) parents: &'static [&'static str],
+    pub(super) is_virtual: bool,
+}
+
+impl MimeTypeChecker for TableType {
+    fn check_with(&self, bytes: &[u8], hits: &RangeHits) -> bool {
+        self.rule.matches_with(bytes, hits)
+    }
+    fn get_rule(&self) -> &'static Rule {
+        self.rule
+    }
+    fn get_priority(&self) -> u32 {
+        self.priority
+    }
+    fn get_mime(&self) -> &'static str {
+        self.mime
+    }
+    fn get_ext(&self) -> &[&'static str] {
+        self.ext
+    }
+    fn get_aliases(&self) -> &[&'static str] {
+        self.aliases
+    }
+    fn get_description(&self) -> Option<&'static str> {
+        self.description
+    }
+    fn get_acronym(&self) -> Option<&'static str> {
+        self.acronym
+    }
+    fn get_uti(&self) -> Option<&'static str> {
+        self.uti
+    }
+    fn get_links(&self) -> &[&'static str] {
+        self.links
+    }
+    fn get_children(&self) -> &[&'static dyn MimeTypeChecker] {
+        self.children
+    }
+    fn get_parents(&self) -> &[&'static str] {
+        self.parents
+    }
+    fn is_virtual(&self) -> bool {
+        self.is_virtual
+    }
+}
@@ -1,9 +1,36 @@
 pub struct ZipSpecialHandler;
+
+impl ZipSpecialHandler {
+    /// The magic the handler looks for
+    pub(crate) const MAGIC: &'static [u8] = b"PK";
+
+    /// The types the handler can find besides the one a `mimetype` entry names,
+    /// which may be any type
+    pub(crate) const TYPES: &'static [&'static str] = &[
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        "application/vnd.openxmlformats-officedocument",
+        "application/vnd.ms-xpsdocument",
+        "application/vnd.android.package-archive",
+        "application/vnd.apple.keynote",
+        "application/vnd.sun.xml.calc",
+        "application/vnd.sun.xml.writer",
+        "application/vnd.sun.xml.impress",
+        "application/vnd.sun.xml.draw",
+    ];
+}
+
 #[cfg(feature = "open_zips")]
 mod zip_impl {
     use crate::magic::{ZipSpecialHandler, MIME_MAP};
     use std::io::{Cursor, Read, Seek};
 
+    /// How much of the `mimetype` entry is read, more than the longest type name
+    const MIMETYPE_LEN: u64 = 256;
+    /// How much of a StarOffice manifest is read for the type of the document
+    const MANIFEST_LEN: u64 = 64 * 1024;
+
     impl ZipSpecialHandler {
         fn has_mimetype_file<R: Read + Seek>(
             &self,
@@ -14,7 +41,11 @@ mod zip_impl {
             };
 
             let mut mimetype = String::new();
-            if mimefile.read_to_string(&mut mimetype).is_err() {
+            if mimefile
+                .take(MIMETYPE_LEN)
+                .read_to_string(&mut mimetype)
+                .is_err()
+            {
                 return None;
             }
 
@@ -90,7 +121,11 @@ mod zip_impl {
             };
 
             let mut mimetype = String::new();
-            if mimefile.read_to_string(&mut mimetype).is_err() {
+            if mimefile
+                .take(MANIFEST_LEN)
+                .read_to_string(&mut mimetype)
+                .is_err()
+            {
                 return None;
             }
 
@@ -119,15 +154,22 @@ mod zip_impl {
         }
 
         pub(crate) fn check(&self, bytes: &[u8]) -> Option<&'static str> {
-            // Make sure it's a zip file
-            let magic = bytes.get(0..2)?;
+            self.check_stream(bytes, Cursor::new(bytes))
+        }
 
-            if magic != b"PK" {
+        /// Checks a whole stream, `head` being the bytes at its start
+        pub(crate) fn check_stream<R: Read + Seek>(
+            &self,
+            head: &[u8],
+            reader: R,
+        ) -> Option<&'static str> {
+            // Make sure it's a zip file
+            if !head.starts_with(Self::MAGIC) {
                 return None;
             }
 
             // Try to open the zip and read its mimetype file
-            let Ok(mut zip) = zip::ZipArchive::new(Cursor::new(bytes)) else {
+            let Ok(mut zip) = zip::ZipArchive::new(reader) else {
                 return None;
             };
 
@@ -0,0 +1,377 @@
+use std::error::Error;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+/// A parsed MIME type, such as `application/dita+xml;format=concept`.
+///
+/// The type and subtype are lowercased, as are parameter names. Parameter values
+/// keep their case and their order.
+///
+/// Two MIME types are equal when their type, subtype and parameters are, whatever
+/// the order of the parameters. Use [`eq_ignore_params`](MediaType::eq_ignore_params)
+/// to compare them ignoring parameters.
+///
+/// # Examples
+/// ```rust
+/// use tika_magic::MediaType;
+///
+/// let media_type: MediaType = "Application/Dita+XML; format=concept".parse().unwrap();
+/// assert_eq!(media_type.type_(), "application");
+/// assert_eq!(media_type.subtype(), "dita+xml");
+/// assert_eq!(media_type.suffix(), Some("xml"));
+/// assert_eq!(media_type.param("format"), Some("concept"));
+/// assert_eq!(media_type.to_string(), "application/dita+xml;format=concept");
+/// ```
+#[derive(Clone, Debug)]
+pub struct MediaType {
+    type_: String,
+    subtype: String,
+    params: Vec<(String, String)>,
+}
+
+/// The error returned when a string is not a valid MIME type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseMediaTypeError {
+    input: String,
+    reason: &'static str,
+}
+
+impl fmt::Display for ParseMediaTypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid MIME type {:?}: {}", self.input, self.reason)
+    }
+}
+
+impl Error for ParseMediaTypeError {}
+
+/// The characters that may not appear in a type, subtype or parameter name, and that
+/// must be quoted in a parameter value (RFC 2045 `tspecials`).
+fn is_special(c: char) -> bool {
+    c.is_ascii_control() || c.is_whitespace() || "()<>@,;:\\\"/[]?=".contains(c)
+}
+
+fn is_token(s: &str) -> bool {
+    !s.is_empty() && !s.chars().any(is_special)
+}
+
+/// Splits a parameter list on `;`, leaving semicolons inside quoted values alone.
+fn split_params(input: &str) -> Result<Vec<&str>, &'static str> {
+    let mut parts = vec![];
+    let mut start = 0;
+    let mut quoted = false;
+    let mut escaped = false;
+
+    for (i, c) in input.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            ';' if !quoted => {
+                parts.push(&input[start..i]);
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+
+    if quoted {
+        return Err("unterminated quoted parameter value");
+    }
+
+    parts.push(&input[start..]);
+    Ok(parts)
+}
+
+fn unquote(value: &str) -> String {
+    match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+        Some(inner) => {
+            let mut unquoted = String::with_capacity(inner.len());
+            let mut chars = inner.chars();
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => unquoted.extend(chars.next()),
+                    _ => unquoted.push(c),
+                }
+            }
+            unquoted
+        }
+        None => value.to_string(),
+    }
+}
+
+impl MediaType {
+    /// Creates a MIME type without parameters.
+    ///
+    /// # Panics
+    /// Panics if the type or subtype is empty or contains characters that are not
+    /// allowed in a MIME type.
+    pub fn new(type_: &str, subtype: &str) -> Self {
+        assert!(is_token(type_), "invalid MIME type: {type_:?}");
+        assert!(is_token(subtype), "invalid MIME subtype: {subtype:?}");
+
+        MediaType {
+            type_: type_.to_ascii_lowercase(),
+            subtype: subtype.to_ascii_lowercase(),
+            params: vec![],
+        }
+    }
+
+    /// Parses a MIME type such as `text/html; charset=utf-8`.
+    pub fn parse(input: &str) -> Result<Self, ParseMediaTypeError> {
+        let error = |reason| ParseMediaTypeError {
+            input: input.to_string(),
+            reason,
+        };
+
+        let mut parts = split_params(input).map_err(error)?.into_iter();
+        let essence = parts.next().unwrap_or_default().trim();
+        let (type_, subtype) = essence
+            .split_once('/')
+            .ok_or_else(|| error("missing '/' between type and subtype"))?;
+
+        if !is_token(type_) || !is_token(subtype) {
+            return Err(error("invalid type or subtype"));
+        }
+
+        let mut media_type = MediaType::new(type_, subtype);
+        for param in parts.map(str::trim).filter(|p| !p.is_empty()) {
+            let (name, value) = param
+                .split_once('=')
+                .ok_or_else(|| error("parameter without a value"))?;
+
+            let name = name.trim();
+            if !is_token(name) {
+                return Err(error("invalid parameter name"));
+            }
+
+            media_type
+                .params
+                .push((name.to_ascii_lowercase(), unquote(value.trim())));
+        }
+
+        Ok(media_type)
+    }
+
+    /// The top-level type, such as `application`.
+    pub fn type_(&self) -> &str {
+        &self.type_
+    }
+
+    /// The subtype, such as `dita+xml`.
+    pub fn subtype(&self) -> &str {
+        &self.subtype
+    }
+
+    /// The structured syntax suffix of the subtype without its `+`, such as `xml`
+    /// for `image/svg+xml`.
+    pub fn suffix(&self) -> Option<&str> {
+        self.subtype
+            .rsplit_once('+')
+            .map(|(_, suffix)| suffix)
+            .filter(|suffix| !suffix.is_empty())
+    }
+
+    /// The `type/subtype` part, without any parameters.
+    pub fn essence(&self) -> String {
+        format!("{}/{}", self.type_, self.subtype)
+    }
+
+    /// The parameters in the order they were given.
+    pub fn params(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.params
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    /// The value of the first parameter with the given name, ignoring case.
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Adds a parameter.
+    ///
+    /// # Panics
+    /// Panics if the name is empty or contains characters that are not allowed in a
+    /// parameter name.
+    pub fn with_param(mut self, name: &str, value: &str) -> Self {
+        assert!(is_token(name), "invalid parameter name: {name:?}");
+        self.params
+            .push((name.to_ascii_lowercase(), value.to_string()));
+        self
+    }
+
+    /// The same MIME type without any parameters.
+    pub fn without_params(&self) -> Self {
+        MediaType {
+            type_: self.type_.clone(),
+            subtype: self.subtype.clone(),
+            params: vec![],
+        }
+    }
+
+    /// Compares the type and subtype, ignoring any parameters.
+    pub fn eq_ignore_params(&self, other: &MediaType) -> bool {
+        self.type_ == other.type_ && self.subtype == other.subtype
+    }
+
+    /// The parameters in a canonical order, for equality and hashing
+    fn sorted_params(&self) -> Vec<&(String, String)> {
+        let mut params = self.params.iter().collect::<Vec<_>>();
+        params.sort();
+        params
+    }
+}
+
+impl PartialEq for MediaType {
+    fn eq(&self, other: &Self) -> bool {
+        self.eq_ignore_params(other) && self.sorted_params() == other.sorted_params()
+    }
+}
+
+impl Eq for MediaType {}
+
+impl Hash for MediaType {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.type_.hash(state);
+        self.subtype.hash(state);
+        self.sorted_params().hash(state);
+    }
+}
+
+impl fmt::Display for MediaType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.type_, self.subtype)?;
+
+        for (name, value) in &self.params {
+            if is_token(value) {
+                write!(f, ";{name}={value}")?;
+            } else {
+                write!(f, ";{name}=\"")?;
+                for c in value.chars() {
+                    if c == '"' || c == '\\' {
+                        f.write_str("\\")?;
+                    }
+                    write!(f, "{c}")?;
+                }
+                f.write_str("\"")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for MediaType {
+    type Err = ParseMediaTypeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        MediaType::parse(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let media_type = MediaType::parse("application/vnd.lotus-1-2-3;version=97+9.x").unwrap();
+        assert_eq!(media_type.type_(), "application");
+        assert_eq!(media_type.subtype(), "vnd.lotus-1-2-3");
+        assert_eq!(media_type.suffix(), None);
+        assert_eq!(media_type.param("VERSION"), Some("97+9.x"));
+
+        let media_type =
+            MediaType::parse(" Text/Plain ; Charset=\"UTF-8\" ; format = flowed ").unwrap();
+        assert_eq!(media_type.essence(), "text/plain");
+        assert_eq!(
+            media_type.params().collect::<Vec<_>>(),
+            vec![("charset", "UTF-8"), ("format", "flowed")]
+        );
+
+        let media_type = MediaType::parse("multipart/mixed; boundary=\"a;b\\\"c\"").unwrap();
+        assert_eq!(media_type.param("boundary"), Some("a;b\"c"));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(MediaType::parse("").is_err());
+        assert!(MediaType::parse("text").is_err());
+        assert!(MediaType::parse("text/").is_err());
+        assert!(MediaType::parse("/plain").is_err());
+        assert!(MediaType::parse("text/pl ain").is_err());
+        assert!(MediaType::parse("text/plain; charset").is_err());
+        assert!(MediaType::parse("text/plain; charset=\"utf-8").is_err());
+    }
+
+    #[test]
+    fn test_suffix() {
+        assert_eq!(MediaType::new("image", "svg+xml").suffix(), Some("xml"));
+        assert_eq!(
+            MediaType::new("application", "epub+zip").suffix(),
+            Some("zip")
+        );
+        assert_eq!(
+            MediaType::new("application", "ld+json").suffix(),
+            Some("json")
+        );
+        assert_eq!(MediaType::new("text", "plain").suffix(), None);
+    }
+
+    #[test]
+    fn test_display_round_trips() {
+        for input in [
+            "application/dita+xml;format=concept",
+            "application/x-berkeley-db;format=btree;version=2",
+            "multipart/mixed;boundary=\"a;b\\\"c\"",
+            "text/plain;charset=\"\"",
+        ] {
+            let media_type = MediaType::parse(input).unwrap();
+            assert_eq!(media_type.to_string(), input);
+            assert_eq!(MediaType::parse(&media_type.to_string()), Ok(media_type));
+        }
+    }
+
+    #[test]
+    fn test_every_known_type_round_trips() {
+        for t in crate::registry::all() {
+            let media_type = MediaType::parse(t.mime()).unwrap();
+            assert_eq!(
+                crate::canonicalize(&media_type.to_string()),
+                Some(t.mime()),
+                "{}",
+                t.mime()
+            );
+        }
+    }
+
+    #[test]
+    fn test_equality() {
+        let html = MediaType::parse("text/html").unwrap();
+        let html_utf8 = MediaType::parse("TEXT/HTML; charset=utf-8").unwrap();
+
+        assert_ne!(html, html_utf8);
+        assert!(html.eq_ignore_params(&html_utf8));
+        assert_eq!(html, html_utf8.without_params());
+        assert_eq!(html_utf8, html.with_param("Charset", "utf-8"));
+
+        let flowed = MediaType::parse("text/plain; charset=utf-8; format=flowed").unwrap();
+        let reordered = MediaType::parse("Text/Plain; Format=flowed; charset=utf-8").unwrap();
+        assert_eq!(flowed, reordered);
+        assert_ne!(
+            flowed,
+            MediaType::parse("text/plain; charset=UTF-16").unwrap()
+        );
+
+        let hash = |media_type: &MediaType| {
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            media_type.hash(&mut hasher);
+            hasher.finish()
+        };
+        assert_eq!(hash(&flowed), hash(&reordered));
+    }
+}
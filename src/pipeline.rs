@@ -0,0 +1,206 @@
+use crate::magic::{file_is_text, MimeTypeChecker, MIME_TYPES};
+use crate::{is_a, Mime};
+use once_cell::sync::Lazy;
+use std::fmt;
+
+/// A step of the detection pipeline of a [`Detector`](crate::Detector).
+///
+/// The stages run in order, like the detectors of Tika's `CompositeDetector`. The
+/// first answer is taken as it is, every later one only if it is a subtype of the
+/// answer so far, so a stage can make the answer more specific but never overrule
+/// it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum PipelineStage {
+    /// Opens ZIP archives and OLE compound documents to tell their formats apart.
+    ///
+    /// Finds nothing when the container is not opened, see
+    /// [`DetectorBuilder::open_zips`](crate::DetectorBuilder::open_zips). TAR
+    /// archives have no subtypes to tell apart, the magic stage finds them.
+    Container,
+    /// Checks the magic of every type, priority types and registered types
+    /// included.
+    Magic,
+    /// Checks the `root-XML` rules of the types below the answer so far, on the
+    /// root element of an XML document.
+    XmlRoot,
+    /// Recognizes text with no magic of its own as `text/plain`.
+    ///
+    /// Not in the default pipeline: Tika's magic database alone does not detect
+    /// plain text.
+    Text,
+    /// Looks up the file name, when one is given, like
+    /// [`from_filename`](crate::from_filename).
+    Name,
+}
+
+impl fmt::Display for PipelineStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            PipelineStage::Container => "container",
+            PipelineStage::Magic => "magic",
+            PipelineStage::XmlRoot => "xml-root",
+            PipelineStage::Text => "text",
+            PipelineStage::Name => "name",
+        })
+    }
+}
+
+/// The stages of the free functions, in order.
+pub(crate) const DEFAULT_PIPELINE: &[PipelineStage] = &[
+    PipelineStage::Container,
+    PipelineStage::Magic,
+    PipelineStage::XmlRoot,
+    PipelineStage::Name,
+];
+
+/// Takes the answer of a stage if it makes the answer so far more specific.
+pub(crate) fn specialize(current: Option<Mime>, detected: Option<Mime>) -> Option<Mime> {
+    match (current, detected) {
+        (None, detected) => detected,
+        (Some(current), Some(detected)) if detected != current && is_a(detected, current) => {
+            Some(detected)
+        }
+        (current, _) => current,
+    }
+}
+
+/// The types with at least one `root-XML` rule, by priority
+static ROOT_XML_TYPES: Lazy<Vec<&'static dyn MimeTypeChecker>> = Lazy::new(|| {
+    MIME_TYPES
+        .iter()
+        .copied()
+        .filter(|checker| checker.get_rule().has_root_xml())
+        .collect()
+});
+
+/// Walks down from the answer so far to the most specific type whose `root-XML`
+/// rule matches.
+pub(crate) fn xml_root(bytes: &[u8], current: Option<Mime>) -> Option<Mime> {
+    if !file_is_text(bytes) {
+        return None;
+    }
+
+    let mut found = None;
+    // Bounded, each step goes one type further down
+    for _ in 0..ROOT_XML_TYPES.len() {
+        let below = found.or(current);
+        let next = ROOT_XML_TYPES.iter().find(|checker| {
+            let mime = checker.get_mime();
+            below.map_or(true, |below| mime != below && is_a(mime, below))
+                && checker.get_rule().matches_root_xml(bytes)
+        });
+
+        match next {
+            Some(checker) => found = Some(checker.get_mime()),
+            None => break,
+        }
+    }
+
+    found
+}
+
+/// How many bytes [`file_is_text`] looks at
+#[cfg(feature = "tokio")]
+const TEXT_CHECK: usize = 128;
+
+/// How many bytes the `root-XML` rules can look at, None if one of them has a regex
+/// looking at everything
+#[cfg(feature = "tokio")]
+static ROOT_XML_REACH: Lazy<Option<usize>> = Lazy::new(|| {
+    ROOT_XML_TYPES.iter().try_fold(0, |reach, checker| {
+        Some(reach.max(checker.get_rule().reach()?))
+    })
+});
+
+/// Whether more bytes after the head could no longer change what [`xml_root`]
+/// finds.
+#[cfg(feature = "tokio")]
+pub(crate) fn xml_root_is_settled(head: &[u8]) -> bool {
+    (head.len() >= TEXT_CHECK && !file_is_text(head))
+        || ROOT_XML_REACH.is_some_and(|reach| reach <= head.len())
+}
+
+/// How much of the input the text heuristics look at
+const TEXT_SAMPLE: usize = 512;
+
+/// Whether more bytes after the head could no longer change what [`text`] finds.
+#[cfg(feature = "tokio")]
+pub(crate) fn text_is_settled(head: &[u8]) -> bool {
+    head.len() >= TEXT_SAMPLE
+}
+
+/// Recognizes text like Tika's `TextDetector`: no control characters other than
+/// whitespace and escape, and either mostly ASCII or valid UTF-8.
+pub(crate) fn text(bytes: &[u8]) -> Option<Mime> {
+    let sample = &bytes[..bytes.len().min(TEXT_SAMPLE)];
+    if sample.is_empty() {
+        return None;
+    }
+
+    let is_control = |b: &u8| (*b < 0x20 && !b"\t\n\x0c\r\x1b".contains(b)) || *b == 0x7f;
+    if sample.iter().any(is_control) {
+        return None;
+    }
+
+    let non_ascii = sample.iter().filter(|b| !b.is_ascii()).count();
+    // A multi-byte character cut off by the end of the sample is still valid
+    let utf8 = match std::str::from_utf8(sample) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none(),
+    };
+
+    (non_ascii * 10 < sample.len() || utf8).then_some("text/plain")
+}
+
+#[cfg(all(test, feature = "all_types"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_specialize() {
+        assert_eq!(
+            specialize(None, Some("application/zip")),
+            Some("application/zip")
+        );
+        assert_eq!(
+            specialize(Some("application/zip"), None),
+            Some("application/zip")
+        );
+        assert_eq!(
+            specialize(Some("application/zip"), Some("application/java-archive")),
+            Some("application/java-archive")
+        );
+        assert_eq!(
+            specialize(Some("application/java-archive"), Some("application/zip")),
+            Some("application/java-archive")
+        );
+        assert_eq!(
+            specialize(Some("application/zip"), Some("image/png")),
+            Some("application/zip")
+        );
+    }
+
+    #[test]
+    fn test_xml_root() {
+        let svg = b"<?xml version=\"1.0\"?>\n<svg xmlns=\"http://www.w3.org/2000/svg\"/>";
+        assert_eq!(
+            xml_root(svg, Some("application/xml")),
+            Some("image/svg+xml")
+        );
+        assert_eq!(xml_root(svg, None), Some("image/svg+xml"));
+        assert_eq!(xml_root(svg, Some("image/svg+xml")), None);
+        assert_eq!(xml_root(svg, Some("application/zip")), None);
+        assert_eq!(xml_root(b"\x89PNG\r\n\x1a\n", None), None);
+    }
+
+    #[test]
+    fn test_text() {
+        assert_eq!(text(b"a,b,c\n1,2,3\n"), Some("text/plain"));
+        assert_eq!(text("Grüße, €".as_bytes()), Some("text/plain"));
+        assert_eq!(text(&"€".as_bytes()[..2]), Some("text/plain"));
+        assert_eq!(text(b"ACME\0\x03"), None);
+        assert_eq!(text(b"\xff\xfe\xfd\xfc"), None);
+        assert_eq!(text(b""), None);
+    }
+}
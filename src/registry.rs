@@ -0,0 +1,383 @@
+//! Read-only access to the MIME types known to tika-magic.
+//!
+//! # Examples
+//! ```rust
+//! use tika_magic::registry;
+//!
+//! // Every type in the database
+//! assert!(registry::all().count() > 1000);
+//!
+//! // Look up a type by name or alias
+//! let zip = registry::lookup("application/x-zip-compressed").unwrap();
+//! assert_eq!(zip.mime(), "application/zip");
+//! assert!(zip.globs().contains(&"*.zip"));
+//! assert!(zip.subtypes().any(|t| t.mime() == "application/java-archive"));
+//! ```
+
+use crate::magic::{MimeTypeChecker, MIME_MAP, MIME_TYPES};
+use crate::Mime;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+#[cfg(any(feature = "custom_mimetypes", feature = "serde"))]
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+#[cfg(any(feature = "custom_mimetypes", feature = "serde"))]
+use std::sync::Mutex;
+
+/// A MIME type from the Tika database.
+#[derive(Clone, Copy)]
+pub struct MimeTypeInfo {
+    checker: &'static dyn MimeTypeChecker,
+}
+
+impl MimeTypeInfo {
+    pub(crate) fn new(checker: &'static dyn MimeTypeChecker) -> Self {
+        MimeTypeInfo { checker }
+    }
+
+    /// The canonical name of the type.
+    pub fn mime(&self) -> Mime {
+        self.checker.get_mime()
+    }
+
+    /// The file name glob patterns of the type, such as `*.zip` or `Makefile`.
+    pub fn globs(&self) -> &'static [&'static str] {
+        self.checker.get_ext()
+    }
+
+    /// The Tika magic priority of the type, from 0 to 100. Higher priority magic is
+    /// checked first. Types without magic have a priority of 0.
+    pub fn priority(&self) -> u32 {
+        self.checker.get_priority()
+    }
+
+    /// The alternative names of the type.
+    pub fn aliases(&self) -> &'static [&'static str] {
+        self.checker.get_aliases()
+    }
+
+    /// A human readable description of the type, such as `Portable Document Format`.
+    pub fn description(&self) -> Option<&'static str> {
+        self.checker.get_description()
+    }
+
+    /// The acronym the type is commonly known by, such as `PDF`.
+    pub fn acronym(&self) -> Option<&'static str> {
+        self.checker.get_acronym()
+    }
+
+    /// The Apple Uniform Type Identifier of the type, such as `com.adobe.pdf`.
+    pub fn uti(&self) -> Option<&'static str> {
+        self.checker.get_uti()
+    }
+
+    /// Links to documentation or specifications of the type.
+    pub fn links(&self) -> &'static [&'static str] {
+        self.checker.get_links()
+    }
+
+    /// The types this type declares itself a `<sub-class-of>`.
+    ///
+    /// Declared parents that are not in the database are skipped.
+    pub fn parents(&self) -> impl Iterator<Item = MimeTypeInfo> {
+        self.checker
+            .get_parents()
+            .iter()
+            .filter_map(|parent| lookup(parent))
+    }
+
+    /// The types that declare themselves a `<sub-class-of>` this type.
+    pub fn subtypes(&self) -> impl Iterator<Item = MimeTypeInfo> {
+        self.checker
+            .get_children()
+            .iter()
+            .map(|child| MimeTypeInfo::new(*child))
+    }
+}
+
+impl fmt::Debug for MimeTypeInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MimeTypeInfo")
+            .field("mime", &self.mime())
+            .field("globs", &self.globs())
+            .field("aliases", &self.aliases())
+            .finish()
+    }
+}
+
+impl fmt::Display for MimeTypeInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.mime())
+    }
+}
+
+impl PartialEq for MimeTypeInfo {
+    fn eq(&self, other: &Self) -> bool {
+        self.mime() == other.mime()
+    }
+}
+
+impl Eq for MimeTypeInfo {}
+
+impl Hash for MimeTypeInfo {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.mime().hash(state);
+    }
+}
+
+/// Iterates over every type in the database.
+pub fn all() -> impl Iterator<Item = MimeTypeInfo> {
+    MIME_TYPES.iter().map(|checker| MimeTypeInfo::new(*checker))
+}
+
+/// Looks up a type by its canonical name or one of its aliases.
+///
+/// The lookup ignores case and any parameters that are not part of a known name,
+/// so `TEXT/XML; charset=utf-8` finds `application/xml`.
+///
+/// Returns None if the type is not in the database.
+pub fn lookup(mimetype: &str) -> Option<MimeTypeInfo> {
+    resolve(mimetype).map(MimeTypeInfo::new)
+}
+
+/// Gets the human readable description of a MIME type or one of its aliases.
+///
+/// # Examples
+/// ```rust
+/// assert_eq!(
+///     tika_magic::description("application/pdf"),
+///     Some("Portable Document Format")
+/// );
+/// ```
+pub fn description(mimetype: &str) -> Option<&'static str> {
+    lookup(mimetype)?.description()
+}
+
+/// Gets the acronym of a MIME type or one of its aliases.
+///
+/// # Examples
+/// ```rust
+/// assert_eq!(tika_magic::acronym("application/pdf"), Some("PDF"));
+/// ```
+pub fn acronym(mimetype: &str) -> Option<&'static str> {
+    lookup(mimetype)?.acronym()
+}
+
+/// Gets the Apple Uniform Type Identifier of a MIME type or one of its aliases.
+///
+/// # Examples
+/// ```rust
+/// assert_eq!(tika_magic::uti("application/pdf"), Some("com.adobe.pdf"));
+/// ```
+pub fn uti(mimetype: &str) -> Option<&'static str> {
+    lookup(mimetype)?.uti()
+}
+
+/// Gets the documentation links of a MIME type or one of its aliases.
+///
+/// Returns an empty slice if the type is not known or has no links.
+///
+/// # Examples
+/// ```rust
+/// assert!(tika_magic::links("application/pdf")
+///     .iter()
+///     .any(|link| link.contains("wikipedia")));
+/// ```
+pub fn links(mimetype: &str) -> &'static [&'static str] {
+    match lookup(mimetype) {
+        Some(t) => t.links(),
+        None => &[],
+    }
+}
+
+/// The names of the types defined or registered at runtime, leaked once each for
+/// the definitions
+#[cfg(any(feature = "custom_mimetypes", feature = "serde"))]
+static NAMES: Lazy<Mutex<HashSet<&'static str>>> = Lazy::new(Default::default);
+
+/// Remembers the name of a type registered with a `TypeDetector`
+#[cfg(any(feature = "custom_mimetypes", feature = "serde"))]
+pub(crate) fn register(mimetype: Mime) {
+    let mut names = NAMES.lock().unwrap_or_else(|e| e.into_inner());
+    names.insert(mimetype);
+}
+
+/// Gets the built-in name of a type, or the name it was defined or registered with
+/// at runtime, None for a type that is neither.
+#[cfg(feature = "serde")]
+pub(crate) fn known_type(mimetype: &str) -> Option<Mime> {
+    if let Some(mime) = crate::canonicalize(mimetype) {
+        return Some(mime);
+    }
+
+    let names = NAMES.lock().unwrap_or_else(|e| e.into_inner());
+    names
+        .get(mimetype)
+        .or_else(|| names.get(normalize(mimetype).as_str()))
+        .copied()
+}
+
+/// Gets a `'static` name for a type, the built-in one if it exists
+#[cfg(feature = "custom_mimetypes")]
+pub(crate) fn intern_type(mimetype: &str) -> Mime {
+    crate::canonicalize(mimetype).unwrap_or_else(|| intern(mimetype))
+}
+
+/// Gets a `'static` copy of a normalized name
+#[cfg(feature = "custom_mimetypes")]
+pub(crate) fn intern(mimetype: &str) -> Mime {
+    let mimetype = normalize(mimetype);
+    let mut names = NAMES.lock().unwrap_or_else(|e| e.into_inner());
+    match names.get(mimetype.as_str()) {
+        Some(name) => name,
+        None => {
+            let name: &'static str = Box::leak(mimetype.into_boxed_str());
+            names.insert(name);
+            name
+        }
+    }
+}
+
+/// Trims whitespace around the parameters of a type and lowercases everything but
+/// the parameter values.
+pub(crate) fn normalize(mimetype: &str) -> String {
+    let mut parts = mimetype.split(';').map(str::trim);
+    let mut normalized = parts.next().unwrap_or_default().to_ascii_lowercase();
+
+    for param in parts.filter(|p| !p.is_empty()) {
+        normalized.push(';');
+        match param.split_once('=') {
+            Some((name, value)) => {
+                normalized.push_str(&name.trim().to_ascii_lowercase());
+                normalized.push('=');
+                normalized.push_str(value.trim());
+            }
+            None => normalized.push_str(&param.to_ascii_lowercase()),
+        }
+    }
+
+    normalized
+}
+
+/// The names and aliases of the database in their normalized form, for the few
+/// that are not lowercase or have spaces around their parameters.
+static NORMALIZED_NAMES: Lazy<HashMap<String, &'static dyn MimeTypeChecker>> = Lazy::new(|| {
+    let mut names = HashMap::new();
+    for (name, checkers) in MIME_MAP.entries() {
+        let normalized = normalize(name);
+        if normalized != *name {
+            if let Some(checker) = checkers.first() {
+                names.insert(normalized, *checker);
+            }
+        }
+    }
+    names
+});
+
+/// Finds the type for an exact or normalized name or alias.
+fn find(mimetype: &str) -> Option<&'static dyn MimeTypeChecker> {
+    match MIME_MAP.get(mimetype) {
+        Some(checkers) => checkers.first().copied(),
+        None => NORMALIZED_NAMES.get(mimetype).copied(),
+    }
+}
+
+pub(crate) fn resolve(mimetype: &str) -> Option<&'static dyn MimeTypeChecker> {
+    if let Some(checker) = MIME_MAP.get(mimetype).and_then(|mm| mm.first()) {
+        return Some(*checker);
+    }
+
+    let normalized = normalize(mimetype);
+    if let Some(checker) = find(&normalized) {
+        return Some(checker);
+    }
+
+    let (base, _) = normalized.split_once(';')?;
+    find(base)
+}
+
+#[cfg(all(test, feature = "all_types"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_all_types_are_unique() {
+        let mut names = all().map(|t| t.mime()).collect::<Vec<_>>();
+        let count = names.len();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), count);
+    }
+
+    #[test]
+    fn test_every_type_can_be_looked_up() {
+        for t in all() {
+            assert_eq!(lookup(t.mime()), Some(t));
+            for alias in t.aliases() {
+                // A few aliases are also claimed by another type, or are the name of
+                // another type. Type names win, then the first type claiming the alias.
+                let aliased = lookup(alias).unwrap();
+                assert!(
+                    aliased.mime() == *alias || aliased.aliases().contains(alias),
+                    "{alias}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_lookup() {
+        let xml = lookup("text/xml").unwrap();
+        assert_eq!(xml.mime(), "application/xml");
+        assert!(xml.aliases().contains(&"text/xml"));
+        assert!(xml.globs().contains(&"*.xml"));
+        assert!(xml.parents().any(|p| p.mime() == "text/plain"));
+
+        assert_eq!(
+            lookup("application/onenote;format=package").map(|t| t.mime()),
+            Some("application/onenote; format=package")
+        );
+
+        assert_eq!(lookup("application/x-not-a-real-type"), None);
+        assert_eq!(lookup(""), None);
+    }
+
+    #[test]
+    fn test_metadata() {
+        let html = lookup("text/html").unwrap();
+        assert_eq!(html.description(), Some("HyperText Markup Language"));
+        assert_eq!(html.acronym(), Some("HTML"));
+        assert_eq!(html.uti(), Some("public.html"));
+        assert_eq!(lookup("application/zip").unwrap().priority(), 50);
+
+        assert_eq!(
+            description("application/x-zip-compressed"),
+            Some("Compressed Archive File")
+        );
+        assert_eq!(uti("application/zip"), Some("com.pkware.zip-archive"));
+        assert_eq!(acronym("application/zip"), None);
+        assert_eq!(description("application/x-not-a-real-type"), None);
+        assert!(links("application/x-not-a-real-type").is_empty());
+
+        assert!(all().filter(|t| t.description().is_some()).count() > 400);
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("Text/HTML"), "text/html");
+        assert_eq!(
+            normalize(" text/html ; Charset = UTF-8 ;"),
+            "text/html;charset=UTF-8"
+        );
+    }
+
+    #[test]
+    fn test_parents_and_subtypes_agree() {
+        for t in all() {
+            for child in t.subtypes() {
+                assert!(child.parents().any(|p| p == t), "{child} -> {t}");
+            }
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use crate::registry::{self, known_type, MimeTypeInfo};
+use crate::{canonicalize, MediaType, Mime};
+use serde::de::{self, Deserializer};
+use serde::ser::{SerializeStruct, Serializer};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+/// Gets the `'static` name of a deserialized type, see [`mime`]
+fn known<E: de::Error>(name: &str) -> Result<Mime, E> {
+    known_type(name).ok_or_else(|| E::custom(format!("unknown MIME type {name:?}")))
+}
+
+/// Deserializes a type name to the built-in name it is or is an alias of, or to the
+/// name of a type defined or registered at runtime. Other names are rejected, so
+/// that untrusted input cannot make up types.
+pub(crate) fn mime<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Mime, D::Error> {
+    let name = Cow::<str>::deserialize(deserializer)?;
+    known(&name)
+}
+
+/// Like [`mime`], for an optional name.
+pub(crate) fn option_mime<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Mime>, D::Error> {
+    let name = Option::<Cow<str>>::deserialize(deserializer)?;
+    name.map(|name| known(&name)).transpose()
+}
+
+/// Like [`mime`], for a list of names.
+pub(crate) fn mimes<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Mime>, D::Error> {
+    let names = Vec::<Cow<str>>::deserialize(deserializer)?;
+    names.iter().map(|name| known(name)).collect()
+}
+
+/// Replaces an alias by the canonical name of the type, keeping the parameters
+fn canonical(media_type: MediaType) -> MediaType {
+    let essence = media_type.essence();
+    match canonicalize(&essence) {
+        Some(mime) if mime != essence => {
+            let canonical = MediaType::parse(mime).expect("canonical MIME types are valid");
+            media_type
+                .params()
+                .fold(canonical, |canonical, (name, value)| {
+                    canonical.with_param(name, value)
+                })
+        }
+        _ => media_type,
+    }
+}
+
+/// Serializes to the canonical string, such as `application/xml;charset=UTF-8` for
+/// `text/xml; charset=UTF-8`.
+impl Serialize for MediaType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&canonical(self.clone()))
+    }
+}
+
+/// Parses a string, replacing an alias by the canonical name of the type.
+impl<'de> Deserialize<'de> for MediaType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let input = Cow::<str>::deserialize(deserializer)?;
+        let media_type = MediaType::parse(&input).map_err(de::Error::custom)?;
+        Ok(canonical(media_type))
+    }
+}
+
+/// Serializes the database entry, with its parents and subtypes by name.
+impl Serialize for MimeTypeInfo {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let names = |types: &mut dyn Iterator<Item = MimeTypeInfo>| {
+            types.map(|t| t.mime()).collect::<Vec<_>>()
+        };
+
+        let mut entry = serializer.serialize_struct("MimeTypeInfo", 10)?;
+        entry.serialize_field("mime", self.mime())?;
+        entry.serialize_field("description", &self.description())?;
+        entry.serialize_field("acronym", &self.acronym())?;
+        entry.serialize_field("uti", &self.uti())?;
+        entry.serialize_field("priority", &self.priority())?;
+        entry.serialize_field("globs", self.globs())?;
+        entry.serialize_field("aliases", self.aliases())?;
+        entry.serialize_field("links", self.links())?;
+        entry.serialize_field("parents", &names(&mut self.parents()))?;
+        entry.serialize_field("subtypes", &names(&mut self.subtypes()))?;
+        entry.end()
+    }
+}
+
+/// Looks the entry up again by its `mime` field, the other fields are ignored.
+impl<'de> Deserialize<'de> for MimeTypeInfo {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct Entry<'a> {
+            #[serde(borrow)]
+            mime: Cow<'a, str>,
+        }
+
+        let entry = Entry::deserialize(deserializer)?;
+        registry::lookup(&entry.mime)
+            .ok_or_else(|| de::Error::custom(format!("unknown MIME type {:?}", entry.mime)))
+    }
+}
+
+#[cfg(all(test, feature = "all_types"))]
+mod tests {
+    use super::*;
+    use crate::{Candidate, Detection, Explanation, Source, Verification};
+
+    fn round_trip<T: Serialize + for<'de> Deserialize<'de>>(value: &T) -> T {
+        let json = serde_json::to_string(value).unwrap();
+        serde_json::from_str(&json).unwrap()
+    }
+
+    #[test]
+    fn test_results() {
+        let data = include_bytes!("../tests/inputs/image/png/png.png");
+
+        let path = std::path::Path::new("./tests/inputs/image/png/png.png");
+        let detection = crate::try_from_filepath(path).unwrap();
+        assert_eq!(round_trip(&detection), detection);
+        assert_eq!(
+            serde_json::to_value(detection).unwrap(),
+            serde_json::json!({ "mime": "image/png", "bytes_read": data.len() })
+        );
+
+        let candidates = crate::from_u8_ranked(data);
+        assert_eq!(round_trip(&candidates), candidates);
+        assert_eq!(serde_json::to_value(Source::XmlRoot).unwrap(), "xml-root");
+
+        let explanation = crate::explain(data);
+        assert_eq!(round_trip(&explanation), explanation);
+
+        let verification = crate::verify(data, "image/png", "cat.png");
+        assert_eq!(round_trip(&verification), verification);
+    }
+
+    #[test]
+    fn test_names_are_canonicalized() {
+        let detection: Detection =
+            serde_json::from_str(r#"{ "mime": "text/xml", "bytes_read": 5 }"#).unwrap();
+        assert_eq!(detection.mime(), "application/xml");
+
+        let candidate: Candidate = serde_json::from_str(
+            r#"{ "mime": "application/x-zip-compressed", "priority": 0, "source": "glob" }"#,
+        )
+        .unwrap();
+        assert_eq!(candidate.mime(), "application/zip");
+        assert_eq!(candidate.source(), Source::Glob);
+
+        let explanation: Result<Explanation, _> = serde_json::from_str(
+            r#"{ "mime": "image/png", "stage": "warp-drive", "path": [], "matches": [] }"#,
+        );
+        assert!(explanation.is_err());
+
+        let verification: Verification = serde_json::from_str(
+            r#"{ "verdict": "declared-alias", "detected": "application/xml", "declared": "text/xml", "from_name": null }"#,
+        )
+        .unwrap();
+        assert_eq!(verification.declared(), Some("application/xml"));
+    }
+
+    #[test]
+    fn test_unknown_names_are_rejected() {
+        for json in [
+            r#"{ "mime": "application/x-never-registered", "bytes_read": 5 }"#,
+            r#"{ "mime": "not a type", "bytes_read": 5 }"#,
+        ] {
+            assert!(serde_json::from_str::<Detection>(json).is_err(), "{json}");
+        }
+        let verification = serde_json::from_str::<Verification>(
+            r#"{ "verdict": "consistent", "detected": "image/png", "declared": "image/x-never-registered", "from_name": null }"#,
+        );
+        assert!(verification.is_err());
+    }
+
+    #[test]
+    fn test_registered_names() {
+        struct Acme;
+        impl crate::TypeDetector for Acme {
+            fn mime(&self) -> Mime {
+                "application/x-acme-serde"
+            }
+            fn check(&self, bytes: &[u8]) -> bool {
+                bytes.starts_with(b"ACME")
+            }
+        }
+
+        let detector = crate::Detector::builder()
+            .add_type_detector(Acme, crate::Placement::BeforeBuiltin)
+            .build()
+            .unwrap();
+        let detection = Detection::new(detector.from_u8(b"ACME"), 4);
+        assert_eq!(detection.mime(), "application/x-acme-serde");
+        assert_eq!(round_trip(&detection), detection);
+    }
+
+    #[test]
+    fn test_media_type() {
+        let media_type: MediaType = "Text/XML; Charset=UTF-8".parse().unwrap();
+        let json = serde_json::to_string(&media_type).unwrap();
+        assert_eq!(json, r#""application/xml;charset=UTF-8""#);
+
+        let parsed: MediaType = serde_json::from_str(r#""text/xml; charset=UTF-8""#).unwrap();
+        assert_eq!(parsed.to_string(), "application/xml;charset=UTF-8");
+        assert_eq!(round_trip(&parsed), parsed);
+
+        let unknown: MediaType = serde_json::from_str(r#""image/x-made-up""#).unwrap();
+        assert_eq!(unknown.to_string(), "image/x-made-up");
+        assert!(serde_json::from_str::<MediaType>(r#""not a type""#).is_err());
+    }
+
+    #[test]
+    fn test_registry_entry() {
+        let zip = registry::lookup("application/zip").unwrap();
+        let json = serde_json::to_value(zip).unwrap();
+        assert_eq!(json["mime"], "application/zip");
+        assert!(json["subtypes"]
+            .as_array()
+            .unwrap()
+            .contains(&"application/java-archive".into()));
+        assert_eq!(serde_json::from_value::<MimeTypeInfo>(json).unwrap(), zip);
+
+        let alias: MimeTypeInfo =
+            serde_json::from_str(r#"{ "mime": "application/x-zip-compressed" }"#).unwrap();
+        assert_eq!(alias, zip);
+        assert!(serde_json::from_str::<MimeTypeInfo>(r#"{ "mime": "image/x-made-up" }"#).is_err());
+    }
+}
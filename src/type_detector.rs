@@ -0,0 +1,312 @@
+use crate::explain::{Found, Stage};
+use crate::magic::{plausible_types, RangeHits};
+use crate::registry::normalize;
+use crate::{canonicalize, check_recursive, Mime};
+use std::fmt;
+use std::sync::Arc;
+
+/// A detector for one MIME type, registered on a [`Detector`](crate::Detector).
+///
+/// Implement it for formats the Tika database does not know, such as proprietary
+/// containers, and add it with
+/// [`DetectorBuilder::add_type_detector`](crate::DetectorBuilder::add_type_detector).
+/// It is then checked by `from_u8` and friends like a built-in type: at the given
+/// [`Placement`], and as a child of the types in [`parents`](Self::parents) once one
+/// of them was detected.
+///
+/// # Examples
+/// ```rust
+/// use tika_magic::{Detector, Mime, Placement, TypeDetector};
+///
+/// // A ZIP based format with a marker file name right after the first header
+/// struct AcmeBundle;
+///
+/// impl TypeDetector for AcmeBundle {
+///     fn mime(&self) -> Mime {
+///         "application/x-acme-bundle"
+///     }
+///
+///     fn check(&self, bytes: &[u8]) -> bool {
+///         bytes.get(30..40) == Some(b"acme.toml\0")
+///     }
+///
+///     fn parents(&self) -> &[&str] {
+///         &["application/zip"]
+///     }
+/// }
+///
+/// let detector = Detector::builder()
+///     .add_type_detector(AcmeBundle, Placement::AfterBuiltin)
+///     .build()
+///     .unwrap();
+///
+/// // Detected as a ZIP first, then refined to the child type
+/// let mut input = b"PK\x03\x04".to_vec();
+/// input.resize(30, 0);
+/// input.extend_from_slice(b"acme.toml\0");
+/// assert_eq!(detector.from_u8(&input), "application/x-acme-bundle");
+/// assert!(detector.match_u8("application/x-acme-bundle", &input));
+/// ```
+pub trait TypeDetector: Send + Sync {
+    /// The MIME type this detector recognizes.
+    ///
+    /// It is returned by the detection functions, so it must live for the whole
+    /// program, like a string literal. A built-in type name adds a way to detect that
+    /// type.
+    fn mime(&self) -> Mime;
+
+    /// Checks if a byte stream is of this type.
+    ///
+    /// Gets the same head of the input the built-in types get.
+    fn check(&self, bytes: &[u8]) -> bool;
+
+    /// Other names of the type, used by `match_u8` and friends.
+    fn aliases(&self) -> &[&str] {
+        &[]
+    }
+
+    /// The types this one is a subtype of.
+    ///
+    /// When one of them is detected, this type is checked next and wins if it
+    /// matches, like built-in subtypes are walked from parent to child.
+    fn parents(&self) -> &[&str] {
+        &[]
+    }
+
+    /// The magic priority of the type, from 0 to 100, used with
+    /// [`Placement::ByPriority`].
+    fn priority(&self) -> u32 {
+        0
+    }
+}
+
+/// Where registered types are checked, relative to the built-in types.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Placement {
+    /// Before every built-in type, including the short list of common types.
+    BeforeBuiltin,
+    /// Among the built-in types, before those with a lower magic priority.
+    ByPriority,
+    /// After every built-in type, only when none of them matched.
+    AfterBuiltin,
+}
+
+/// The types registered on a [`Detector`](crate::Detector), by placement.
+#[derive(Clone)]
+pub(crate) struct CustomTypes {
+    before: Vec<Arc<dyn TypeDetector>>,
+    by_priority: Vec<Arc<dyn TypeDetector>>,
+    after: Vec<Arc<dyn TypeDetector>>,
+}
+
+impl CustomTypes {
+    pub(crate) const EMPTY: CustomTypes = CustomTypes {
+        before: Vec::new(),
+        by_priority: Vec::new(),
+        after: Vec::new(),
+    };
+
+    pub(crate) fn add(&mut self, detector: Arc<dyn TypeDetector>, placement: Placement) {
+        match placement {
+            Placement::BeforeBuiltin => self.before.push(detector),
+            Placement::AfterBuiltin => self.after.push(detector),
+            Placement::ByPriority => {
+                self.by_priority.push(detector);
+                // Stable, so types of the same priority keep the order they were added in
+                self.by_priority
+                    .sort_by_key(|t| std::cmp::Reverse(t.priority()));
+            }
+        }
+    }
+
+    fn all(&self) -> impl Iterator<Item = &Arc<dyn TypeDetector>> {
+        self.before
+            .iter()
+            .chain(&self.by_priority)
+            .chain(&self.after)
+    }
+
+    #[cfg(feature = "tokio")]
+    pub(crate) fn is_empty(&self) -> bool {
+        self.all().next().is_none()
+    }
+
+    pub(crate) fn mime_types(&self) -> Vec<Mime> {
+        self.all().map(|t| t.mime()).collect()
+    }
+
+    pub(crate) fn check_before(&self, bytes: &[u8]) -> Option<Mime> {
+        self.before
+            .iter()
+            .find(|t| t.check(bytes))
+            .map(|t| t.mime())
+    }
+
+    /// Checks the built-in types that can match, with the types placed by priority
+    /// merged in
+    pub(crate) fn check_main(&self, bytes: &[u8], hits: &RangeHits) -> Option<Found> {
+        let mut custom = self.by_priority.iter().peekable();
+        for checker in plausible_types(bytes) {
+            while let Some(t) = custom.next_if(|t| t.priority() > checker.get_priority()) {
+                if t.check(bytes) {
+                    return Some(Found::new(t.mime(), Stage::Custom));
+                }
+            }
+
+            if let Some(mime) = check_recursive(checker, bytes, hits) {
+                return Some(Found::walked(mime, Stage::MainList, checker));
+            }
+        }
+
+        custom
+            .find(|t| t.check(bytes))
+            .map(|t| Found::new(t.mime(), Stage::Custom))
+    }
+
+    pub(crate) fn check_after(&self, bytes: &[u8]) -> Option<Mime> {
+        self.after.iter().find(|t| t.check(bytes)).map(|t| t.mime())
+    }
+
+    /// Walks down from a detected type to the registered types that are a subtype
+    /// of it, like the built-in types are walked from parent to child.
+    pub(crate) fn refine(&self, mut mime: Mime, bytes: &[u8]) -> Mime {
+        let is_child = |t: &Arc<dyn TypeDetector>, mime: Mime| {
+            t.mime() != mime
+                && t.parents()
+                    .iter()
+                    .any(|p| *p == mime || canonicalize(p) == Some(mime))
+        };
+
+        // Bounded, in case the registered types have a cycle of parents
+        for _ in 0..self.all().count() {
+            match self.all().find(|t| is_child(t, mime) && t.check(bytes)) {
+                Some(child) => mime = child.mime(),
+                None => break,
+            }
+        }
+
+        mime
+    }
+
+    /// The registered types with a name or alias
+    fn named<'a>(&'a self, mimetype: &str) -> impl Iterator<Item = &'a Arc<dyn TypeDetector>> {
+        let mimetype = normalize(mimetype);
+        self.all().filter(move |t| {
+            t.mime() == mimetype || t.aliases().iter().any(|a| normalize(a) == mimetype)
+        })
+    }
+
+    /// Checks a registered type by name or alias, None if no type has that name
+    pub(crate) fn matches(&self, mimetype: &str, bytes: &[u8]) -> Option<bool> {
+        let mut named = self.named(mimetype).peekable();
+        named.peek()?;
+        Some(named.any(|t| t.check(bytes)))
+    }
+
+    /// Finds the registered type with a name or alias that matches
+    pub(crate) fn find_match(&self, mimetype: &str, bytes: &[u8]) -> Option<Mime> {
+        // Skips normalizing the name when nothing is registered
+        self.all().next()?;
+        self.named(mimetype)
+            .find(|t| t.check(bytes))
+            .map(|t| t.mime())
+    }
+}
+
+impl fmt::Debug for CustomTypes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.mime_types()).finish()
+    }
+}
+
+#[cfg(all(test, feature = "all_types"))]
+mod tests {
+    use super::*;
+    use crate::Detector;
+
+    struct Marker {
+        mime: Mime,
+        marker: &'static [u8],
+        parents: &'static [&'static str],
+        priority: u32,
+    }
+
+    impl TypeDetector for Marker {
+        fn mime(&self) -> Mime {
+            self.mime
+        }
+
+        fn check(&self, bytes: &[u8]) -> bool {
+            bytes.starts_with(self.marker)
+        }
+
+        fn aliases(&self) -> &[&str] {
+            &["application/x-marker-alias"]
+        }
+
+        fn parents(&self) -> &[&str] {
+            self.parents
+        }
+
+        fn priority(&self) -> u32 {
+            self.priority
+        }
+    }
+
+    const PNG_LIKE: Marker = Marker {
+        mime: "image/x-png-like",
+        marker: b"\x89PNG",
+        parents: &[],
+        priority: 10,
+    };
+
+    #[test]
+    fn test_placement() {
+        let data = include_bytes!("../tests/inputs/image/png/png.png");
+        let detector = |placement| {
+            Detector::builder()
+                .add_type_detector(PNG_LIKE, placement)
+                .build()
+                .unwrap()
+        };
+
+        let before = detector(Placement::BeforeBuiltin);
+        assert_eq!(before.from_u8(data), "image/x-png-like");
+        assert_eq!(before.custom_types(), ["image/x-png-like"]);
+
+        // The built-in PNG magic has a higher priority
+        let by_priority = detector(Placement::ByPriority);
+        assert_eq!(by_priority.from_u8(data), "image/png");
+        assert!(by_priority.match_u8("Application/X-Marker-Alias", data));
+
+        let after = detector(Placement::AfterBuiltin);
+        assert_eq!(after.from_u8(data), "image/png");
+        assert_eq!(after.from_u8(b"\x89PNG, but no more"), "image/x-png-like");
+    }
+
+    #[test]
+    fn test_children() {
+        let xml = b"<?xml version=\"1.0\"?><acme/>";
+        let acme = Marker {
+            mime: "application/x-acme+xml",
+            marker: b"<?xml version=\"1.0\"?><acme",
+            parents: &["text/xml"],
+            priority: 0,
+        };
+        let acme_v2 = Marker {
+            mime: "application/x-acme-v2+xml",
+            marker: b"<?xml version=\"1.0\"?><acme/>",
+            parents: &["application/x-acme+xml"],
+            priority: 0,
+        };
+
+        let detector = Detector::builder()
+            .add_type_detector(acme_v2, Placement::AfterBuiltin)
+            .add_type_detector(acme, Placement::AfterBuiltin)
+            .build()
+            .unwrap();
+        assert_eq!(crate::from_u8(xml), "application/xml");
+        assert_eq!(detector.from_u8(xml), "application/x-acme-v2+xml");
+    }
+}
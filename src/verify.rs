@@ -0,0 +1,280 @@
+use crate::detector::DEFAULT_DETECTOR;
+use crate::registry::normalize;
+use crate::{canonicalize, from_filename, is_a, Detector, Mime};
+use std::fmt;
+
+/// How a declared type and file name compare to the contents, see [`verify`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+#[non_exhaustive]
+pub enum Verdict {
+    /// The declared type is the detected type, and the file name does not disagree.
+    Consistent,
+    /// The declared type is an alias of the detected type, like `text/xml` for
+    /// `application/xml`.
+    DeclaredAlias,
+    /// The declared type is a supertype of the detected type, like `application/zip`
+    /// for a DOCX.
+    DeclaredSupertype,
+    /// The declared type is a subtype of the detected type, like a DOCX declared
+    /// for contents only recognized as `application/zip`.
+    DeclaredSubtype,
+    /// The declared type fits the contents, but the file name extension is for an
+    /// unrelated type.
+    ExtensionMismatch,
+    /// The declared type and the contents are unrelated, like an executable
+    /// declared as `image/png`.
+    Contradiction,
+    /// The contents are only recognized as a generic type, `application/octet-stream`
+    /// or `text/plain`, which cannot confirm the more specific declared type, like
+    /// unknown bytes declared as `image/png`.
+    Unverified,
+}
+
+impl Verdict {
+    /// Whether the declaration is a lie or a mistake worth rejecting: an
+    /// [`ExtensionMismatch`](Self::ExtensionMismatch), a
+    /// [`Contradiction`](Self::Contradiction) or a declaration the contents leave
+    /// [`Unverified`](Self::Unverified).
+    pub fn is_mismatch(&self) -> bool {
+        matches!(
+            self,
+            Verdict::ExtensionMismatch | Verdict::Contradiction | Verdict::Unverified
+        )
+    }
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Verdict::Consistent => "consistent",
+            Verdict::DeclaredAlias => "declared type is an alias",
+            Verdict::DeclaredSupertype => "declared type is a supertype",
+            Verdict::DeclaredSubtype => "declared type is a subtype",
+            Verdict::ExtensionMismatch => "extension mismatch",
+            Verdict::Contradiction => "contradiction",
+            Verdict::Unverified => "unverified",
+        })
+    }
+}
+
+/// The result of [`verify`]: the verdict and the types it is based on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Verification {
+    verdict: Verdict,
+    #[cfg_attr(
+        feature = "serde",
+        serde(deserialize_with = "crate::serialization::mime")
+    )]
+    detected: Mime,
+    #[cfg_attr(
+        feature = "serde",
+        serde(deserialize_with = "crate::serialization::option_mime")
+    )]
+    declared: Option<Mime>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(deserialize_with = "crate::serialization::option_mime")
+    )]
+    from_name: Option<Mime>,
+}
+
+impl Verification {
+    /// How the declared type and file name compare to the contents.
+    pub fn verdict(&self) -> Verdict {
+        self.verdict
+    }
+
+    /// The type detected from the contents.
+    pub fn detected(&self) -> Mime {
+        self.detected
+    }
+
+    /// The canonical name of the declared type, None if it is not known.
+    pub fn declared(&self) -> Option<Mime> {
+        self.declared
+    }
+
+    /// The type of the file name, None if its extension is not known.
+    pub fn from_name(&self) -> Option<Mime> {
+        self.from_name
+    }
+}
+
+impl fmt::Display for Verification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (detected {})", self.verdict, self.detected)
+    }
+}
+
+/// Whether the detected type is a root of the hierarchy that says nothing about
+/// the declared type: any type is a kind of `application/octet-stream`, and many
+/// that are not text, like XML, are a kind of `text/plain`.
+fn is_generic(detected: Mime, declared: &str) -> bool {
+    detected == "application/octet-stream"
+        || (detected == "text/plain" && !declared.starts_with("text/"))
+}
+
+/// Compares the declared type with the detected one
+fn compare(declared: &str, detected: Mime) -> Verdict {
+    let normalized = normalize(declared);
+    let base = normalized.split(';').next().unwrap_or_default();
+    let canonical = canonicalize(declared);
+
+    if base == detected {
+        Verdict::Consistent
+    } else if canonical == Some(detected) {
+        Verdict::DeclaredAlias
+    } else if is_a(detected, declared) {
+        Verdict::DeclaredSupertype
+    } else if is_generic(detected, &normalized) {
+        Verdict::Unverified
+    } else if is_a(declared, detected) {
+        Verdict::DeclaredSubtype
+    } else {
+        Verdict::Contradiction
+    }
+}
+
+impl Detector {
+    /// Checks a declared type and file name against the contents of an upload.
+    ///
+    /// See [`verify`](crate::verify).
+    pub fn verify(&self, bytes: &[u8], declared: &str, filename: &str) -> Verification {
+        let detected = self.from_u8(bytes);
+        let from_name = from_filename(filename);
+
+        let mut verdict = compare(declared, detected);
+        let unrelated_name =
+            from_name.is_some_and(|name| !is_a(name, detected) && !is_a(detected, name));
+        if unrelated_name && !verdict.is_mismatch() {
+            verdict = Verdict::ExtensionMismatch;
+        }
+
+        Verification {
+            verdict,
+            detected,
+            declared: canonicalize(declared),
+            from_name,
+        }
+    }
+}
+
+/// Checks the type and file name a client declared for an upload against its
+/// contents.
+///
+/// The type is detected from the contents alone, so neither the declared type nor
+/// the file name can sway it. The declared type, which may have parameters like a
+/// `Content-Type` header, is then compared to it through the aliases and
+/// `sub-class-of` relations of the database. A file name whose extension is for a
+/// type unrelated to the contents turns an otherwise fitting declaration into an
+/// [`ExtensionMismatch`](Verdict::ExtensionMismatch). An unknown extension is
+/// ignored. Contents only recognized as `application/octet-stream`, or as
+/// `text/plain` for a type that is not text, leave the declared type
+/// [`Unverified`](Verdict::Unverified).
+///
+/// # Examples
+/// ```rust
+/// use tika_magic::Verdict;
+///
+/// // Load a DOCX file
+/// let input: &[u8] = include_bytes!("../tests/inputs/application/vnd.openxmlformats-officedocument.wordprocessingml.document/testWORD.docx");
+///
+/// let docx = "application/vnd.openxmlformats-officedocument.wordprocessingml.document";
+/// assert_eq!(tika_magic::verify(input, docx, "report.docx").verdict(), Verdict::Consistent);
+/// assert_eq!(
+///     tika_magic::verify(input, "application/zip", "report.docx").verdict(),
+///     Verdict::DeclaredSupertype
+/// );
+/// assert_eq!(tika_magic::verify(input, docx, "report.pdf").verdict(), Verdict::ExtensionMismatch);
+/// assert_eq!(tika_magic::verify(input, "image/png", "report.png").verdict(), Verdict::Contradiction);
+/// ```
+pub fn verify(bytes: &[u8], declared: &str, filename: &str) -> Verification {
+    DEFAULT_DETECTOR.verify(bytes, declared, filename)
+}
+
+#[cfg(all(test, feature = "all_types"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare() {
+        assert_eq!(
+            compare("Application/XML; charset=UTF-8", "application/xml"),
+            Verdict::Consistent
+        );
+        assert_eq!(
+            compare("text/xml", "application/xml"),
+            Verdict::DeclaredAlias
+        );
+        assert_eq!(
+            compare("application/xml", "image/svg+xml"),
+            Verdict::DeclaredSupertype
+        );
+        assert_eq!(
+            compare("application/java-archive", "application/zip"),
+            Verdict::DeclaredSubtype
+        );
+        assert_eq!(
+            compare("image/x-made-up", "application/octet-stream"),
+            Verdict::Unverified
+        );
+        assert_eq!(compare("text/csv", "text/plain"), Verdict::DeclaredSubtype);
+        assert_eq!(
+            compare("application/xml", "text/plain"),
+            Verdict::Unverified
+        );
+        assert_eq!(
+            compare("application/octet-stream", "application/octet-stream"),
+            Verdict::Consistent
+        );
+        assert_eq!(
+            compare("image/x-made-up", "image/png"),
+            Verdict::Contradiction
+        );
+    }
+
+    #[test]
+    fn test_executable_as_image() {
+        let data = include_bytes!("../tests/inputs/application/x-executable/testLinux-x86-64");
+        let verification = verify(data, "image/png", "cat.png");
+        assert_eq!(verification.verdict(), Verdict::Contradiction);
+        assert!(verification.verdict().is_mismatch());
+        assert_eq!(verification.detected(), "application/x-executable");
+        assert_eq!(verification.declared(), Some("image/png"));
+        assert_eq!(verification.from_name(), Some("image/png"));
+        assert_eq!(
+            verification.to_string(),
+            "contradiction (detected application/x-executable)"
+        );
+    }
+
+    #[test]
+    fn test_unknown_bytes_as_image() {
+        let data = [0x13, 0x37, 0xC0, 0xDE, 0x00, 0xFF, 0x7F, 0x80].repeat(64);
+        let verification = verify(&data, "image/png", "cat.png");
+        assert_eq!(verification.detected(), "application/octet-stream");
+        assert_eq!(verification.verdict(), Verdict::Unverified);
+        assert!(verification.verdict().is_mismatch());
+    }
+
+    #[test]
+    fn test_extension() {
+        let data = include_bytes!("../tests/inputs/image/png/png.png");
+        assert_eq!(
+            verify(data, "image/png", "cat.exe").verdict(),
+            Verdict::ExtensionMismatch
+        );
+        assert_eq!(
+            verify(data, "image/png", "cat.unknown-extension").verdict(),
+            Verdict::Consistent
+        );
+        assert_eq!(verify(data, "image/png", "").verdict(), Verdict::Consistent);
+        assert!(!verify(data, "IMAGE/PNG", "CAT.PNG").verdict().is_mismatch());
+    }
+}
@@ -1,8 +1,8 @@
-#[cfg(all(test, feature = "open_zips"))]
+#[cfg(all(test, feature = "all_types", feature = "open_zips"))]
 mod tests {
     use rstest::rstest;
     use std::path::{Path, PathBuf};
-    use tika_magic::from_filepath;
+    use tika_magic::{from_filepath, is_a, match_filepath, match_filepath_or_subtype};
 
     // Helper to extract expected MIME type from the file path
     fn expected_mime_type(path: &Path) -> String {
@@ -33,4 +33,66 @@ mod tests {
             detected_mime
         );
     }
+
+    #[rstest]
+    fn test_mime_matching(#[files("tests/inputs/*/*/*.*")] path: PathBuf) {
+        let expected_mime = expected_mime_type(&path);
+        assert!(match_filepath(&expected_mime, &path), "{expected_mime}");
+        assert!(
+            match_filepath_or_subtype(&expected_mime, &path),
+            "{expected_mime}"
+        );
+    }
+
+    #[rstest]
+    fn test_mime_matching_rejects_unrelated_types(
+        #[files("tests/inputs/*/*/*.*")] path: PathBuf,
+        #[values("image/png", "image/gif", "application/pdf", "audio/mpeg")] other: &str,
+    ) {
+        let expected_mime = expected_mime_type(&path);
+        if is_a(&expected_mime, other) || is_a(other, &expected_mime) {
+            return;
+        }
+
+        assert!(!match_filepath(other, &path), "{expected_mime}");
+        assert!(!match_filepath_or_subtype(other, &path), "{expected_mime}");
+    }
+
+    #[rstest]
+    fn test_mime_matching_accepts_supertypes(#[files("tests/inputs/*/*/*.*")] path: PathBuf) {
+        let expected_mime = expected_mime_type(&path);
+        for supertype in tika_magic::supertypes(&expected_mime) {
+            assert!(
+                match_filepath_or_subtype(supertype, &path),
+                "{expected_mime} is a {supertype}"
+            );
+        }
+    }
+
+    #[rstest]
+    fn test_explain_agrees_with_detection(#[files("tests/inputs/*/*/*.*")] path: PathBuf) {
+        let mut data = std::fs::read(&path).unwrap();
+        data.truncate(0x20000);
+
+        let explanation = tika_magic::explain(&data);
+        assert_eq!(explanation.mime(), tika_magic::from_u8(&data));
+        if let Some(last) = explanation.path().last() {
+            assert_eq!(*last, explanation.mime());
+            assert!(!explanation.matches().is_empty(), "{explanation}");
+        }
+    }
+
+    #[rstest]
+    fn test_ranked_agrees_with_detection(#[files("tests/inputs/*/*/*.*")] path: PathBuf) {
+        let ranked = tika_magic::from_filepath_ranked(&path).unwrap();
+        assert_eq!(
+            ranked[0].mime(),
+            tika_magic::from_filepath_with_name(&path).unwrap()
+        );
+
+        let mut mimes = ranked.iter().map(|c| c.mime()).collect::<Vec<_>>();
+        mimes.sort();
+        mimes.dedup();
+        assert_eq!(mimes.len(), ranked.len());
+    }
 }
@@ -0,0 +1,36 @@
+//! Run with `--no-default-features --features image`, which keeps the images and
+//! only the types of other families they subclass.
+#![cfg(all(feature = "image", not(feature = "all_types")))]
+
+const PNG: &[u8] = include_bytes!("inputs/image/png/png.png");
+const SVG: &[u8] = include_bytes!("inputs/image/svg+xml/svg.svg");
+const DOCX: &[u8] = include_bytes!(
+    "inputs/application/vnd.openxmlformats-officedocument.wordprocessingml.document/footnotes.docx"
+);
+
+#[test]
+fn test_images_are_kept() {
+    assert_eq!(tika_magic::from_u8(PNG), "image/png");
+    assert!(tika_magic::registry::lookup("image/png").is_some());
+}
+
+#[test]
+fn test_other_families_are_left_out() {
+    assert!(tika_magic::registry::lookup("application/pdf").is_none());
+    assert!(tika_magic::registry::lookup("audio/mpeg").is_none());
+    assert!(tika_magic::registry::lookup(
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document"
+    )
+    .is_none());
+    assert_eq!(tika_magic::from_u8(DOCX), "application/octet-stream");
+}
+
+#[test]
+fn test_parents_of_images_are_kept() {
+    assert_eq!(tika_magic::from_u8(SVG), "image/svg+xml");
+    assert!(tika_magic::registry::lookup("application/xml").is_some());
+    assert_eq!(
+        tika_magic::supertypes("image/svg+xml"),
+        vec!["application/xml", "text/plain", "application/octet-stream"]
+    );
+}
@@ -1,3 +1,4 @@
+#[cfg(feature = "all_types")]
 mod tests {
     use rstest::rstest;
     use tika_magic::from_u8;
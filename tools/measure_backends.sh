@@ -0,0 +1,56 @@
+#!/usr/bin/env bash
+# Measures what the `rule_table` feature changes: how long tika-magic itself takes to
+# compile, in debug and release, how large its library is, and how large a binary
+# using it is, the `tools/file` CLI in release.
+#
+# Each backend builds in its own target directory so neither reuses the other's
+# artifacts. Dependencies are built first and left out of the timings, and so is
+# incremental compilation, which would only replay the previous debug build.
+set -euo pipefail
+
+export CARGO_INCREMENTAL=0
+
+root="$(cd "$(dirname "$0")/.." && pwd)"
+target="${TARGET_DIR:-$root/target/measure}"
+
+seconds() {
+    local start end
+    start=$(date +%s.%N)
+    "$@" >/dev/null 2>&1
+    end=$(date +%s.%N)
+    awk "BEGIN { printf \"%.1f\", $end - $start }"
+}
+
+size() {
+    wc -c <"$1" | tr -d ' '
+}
+
+printf '%-12s %-8s %14s %14s %14s\n' backend profile "compile (s)" "rlib (bytes)" "cli (bytes)"
+for backend in generated rule_table; do
+    features=()
+    if [ "$backend" = rule_table ]; then
+        features=(--features rule_table)
+    fi
+
+    for profile in debug release; do
+        flags=(--manifest-path "$root/Cargo.toml" --lib "${features[@]}")
+        if [ "$profile" = release ]; then
+            flags+=(--release)
+        fi
+
+        export CARGO_TARGET_DIR="$target/$backend"
+        cargo build "${flags[@]}" >/dev/null 2>&1
+        touch "$root/src/lib.rs"
+        compile=$(seconds cargo build "${flags[@]}")
+        rlib=$(size "$CARGO_TARGET_DIR/$profile/libtika_magic.rlib")
+
+        cli=-
+        if [ "$profile" = release ]; then
+            cargo build --manifest-path "$root/tools/file/Cargo.toml" --release \
+                "${features[@]/rule_table/tika-magic/rule_table}" >/dev/null 2>&1
+            cli=$(size "$CARGO_TARGET_DIR/release/tika-magic-file")
+        fi
+
+        printf '%-12s %-8s %14s %14s %14s\n' "$backend" "$profile" "$compile" "$rlib" "$cli"
+    done
+done
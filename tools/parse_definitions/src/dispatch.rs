@@ -0,0 +1,184 @@
+use crate::MatchRule;
+use std::collections::BTreeSet;
+
+/// The offsets the dispatch index is keyed on: the first byte, the box type of an
+/// ISO-BMFF `ftyp` box and the `ustar` magic of a tar header
+pub const DISPATCH_OFFSETS: [u32; 3] = [0, 4, 257];
+
+/// Stands for an input that ends before the offset
+pub const END_OF_INPUT: u16 = 256;
+
+/// The values a byte can have for a rule to match, with [`END_OF_INPUT`] for a
+/// missing byte, or None if the rule does not look at that byte
+pub type ByteSet = Option<BTreeSet<u16>>;
+
+/// The bytes a rule allows at an offset.
+///
+/// This mirrors the helpers of the generated `check()`: `offset` needs the whole
+/// needle to be there, while `offset_case_insensitive` and `offset_mask` match an
+/// input that ends partway through the needle.
+pub fn bytes_at(rule: &MatchRule, at: u32) -> ByteSet {
+    let index = |start: u32, len: usize| {
+        at.checked_sub(start)
+            .map(|i| i as usize)
+            .filter(|i| *i < len)
+    };
+
+    match rule {
+        MatchRule::String(start, needle)
+        | MatchRule::ValueU32(start, needle)
+        | MatchRule::ValueU16(start, needle)
+        | MatchRule::UnicodeLE(start, needle) => {
+            let i = index(*start, needle.len())?;
+            Some(BTreeSet::from([u16::from(needle[i])]))
+        }
+        MatchRule::StringCaseInsensitive(start, needle) => {
+            let i = index(*start, needle.len())?;
+            Some(BTreeSet::from([
+                u16::from(needle[i].to_ascii_lowercase()),
+                u16::from(needle[i].to_ascii_uppercase()),
+                END_OF_INPUT,
+            ]))
+        }
+        MatchRule::StringMask(start, needle, mask) => {
+            let i = index(*start, needle.len().min(mask.len()))?;
+            let mut set = (0..=255u8)
+                .filter(|byte| byte & mask[i] == needle[i] & mask[i])
+                .map(u16::from)
+                .collect::<BTreeSet<_>>();
+            set.insert(END_OF_INPUT);
+            Some(set)
+        }
+        MatchRule::And(rules) => rules
+            .iter()
+            .filter_map(|rule| bytes_at(rule, at))
+            .reduce(|a, b| a.intersection(&b).copied().collect()),
+        MatchRule::Or(rules) => rules.iter().try_fold(BTreeSet::new(), |mut set, rule| {
+            set.extend(bytes_at(rule, at)?);
+            Some(set)
+        }),
+        MatchRule::Empty => Some(BTreeSet::new()),
+        _ => None,
+    }
+}
+
+/// Joins the byte sets of two rules either of which may match
+fn union(a: &[ByteSet], b: &[ByteSet]) -> Vec<ByteSet> {
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| Some(a.as_ref()?.union(b.as_ref()?).copied().collect()))
+        .collect()
+}
+
+/// The byte sets of a type and all its descendants, since `check_recursive` walks
+/// the children of a type that has any whether or not it matches
+fn subtree(
+    rules: &[Vec<ByteSet>],
+    children: &[Vec<usize>],
+    i: usize,
+    seen: &mut Vec<usize>,
+) -> Vec<ByteSet> {
+    seen.push(i);
+    let mut sets = rules[i].clone();
+    for child in &children[i] {
+        if !seen.contains(child) {
+            sets = union(&sets, &subtree(rules, children, *child, seen));
+        }
+    }
+    seen.pop();
+    sets
+}
+
+/// Which types can match, by the byte at each of [`DISPATCH_OFFSETS`].
+///
+/// Each type is listed under the offset that rules it out for the most inputs, or
+/// in `any` if none does. The lists hold indexes in the sorted types, ascending.
+#[derive(Clone)]
+pub struct DispatchIndex {
+    pub tables: Vec<Vec<Vec<usize>>>,
+    pub any: Vec<usize>,
+}
+
+impl DispatchIndex {
+    /// Builds the index from the byte sets of the rule of each type and the indexes
+    /// of its children
+    pub fn new(rules: &[Vec<ByteSet>], children: &[Vec<usize>]) -> Self {
+        let mut index = DispatchIndex {
+            tables: vec![vec![vec![]; usize::from(END_OF_INPUT) + 1]; DISPATCH_OFFSETS.len()],
+            any: vec![],
+        };
+
+        for i in 0..rules.len() {
+            let sets = subtree(rules, children, i, &mut vec![]);
+            let best = sets
+                .iter()
+                .enumerate()
+                .filter_map(|(offset, set)| Some((offset, set.as_ref()?)))
+                .filter(|(_, set)| set.len() <= usize::from(END_OF_INPUT))
+                .min_by_key(|(_, set)| set.len());
+
+            match best {
+                Some((offset, set)) => {
+                    for byte in set {
+                        index.tables[offset][usize::from(*byte)].push(i);
+                    }
+                }
+                None => index.any.push(i),
+            }
+        }
+
+        index
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bytes_at() {
+        let zip = MatchRule::Or(vec![
+            MatchRule::String(0, b"PK\x03\x04".to_vec()),
+            MatchRule::String(0, b"PK\x05\x06".to_vec()),
+        ]);
+        assert_eq!(bytes_at(&zip, 0), Some(BTreeSet::from([u16::from(b'P')])));
+        assert_eq!(bytes_at(&zip, 4), None);
+
+        let tar = MatchRule::String(257, b"ustar".to_vec());
+        assert_eq!(bytes_at(&tar, 0), None);
+        assert_eq!(bytes_at(&tar, 257), Some(BTreeSet::from([u16::from(b'u')])));
+
+        let html = MatchRule::StringCaseInsensitive(0, b"<html".to_vec());
+        assert_eq!(
+            bytes_at(&html, 0),
+            Some(BTreeSet::from([u16::from(b'<'), END_OF_INPUT]))
+        );
+
+        let mask = MatchRule::StringMask(0, vec![0x10], vec![0xF0]);
+        assert_eq!(bytes_at(&mask, 0).unwrap().len(), 17);
+
+        let either = MatchRule::Or(vec![zip.clone(), MatchRule::Regex(0, "^a".into())]);
+        assert_eq!(bytes_at(&either, 0), None);
+
+        let both = MatchRule::And(vec![MatchRule::Regex(0, "^a".into()), zip]);
+        assert_eq!(bytes_at(&both, 0), Some(BTreeSet::from([u16::from(b'P')])));
+        assert_eq!(bytes_at(&MatchRule::Empty, 0), Some(BTreeSet::new()));
+    }
+
+    #[test]
+    fn test_index_includes_descendants() {
+        let png = vec![Some(BTreeSet::from([0x89])), None, None];
+        let gif = vec![Some(BTreeSet::from([u16::from(b'G')])), None, None];
+        let text = vec![None, None, None];
+
+        let index = DispatchIndex::new(&[png, gif.clone(), text], &[vec![], vec![], vec![]]);
+        assert_eq!(index.tables[0][0x89], vec![0]);
+        assert_eq!(index.tables[0][usize::from(b'G')], vec![1]);
+        assert_eq!(index.any, vec![2]);
+
+        let parent = vec![Some(BTreeSet::from([u16::from(b'<')])), None, None];
+        let index = DispatchIndex::new(&[parent, gif], &[vec![1], vec![]]);
+        assert_eq!(index.tables[0][usize::from(b'<')], vec![0]);
+        assert_eq!(index.tables[0][usize::from(b'G')], vec![0, 1]);
+    }
+}
@@ -0,0 +1,187 @@
+use std::collections::BTreeSet;
+
+/// The top-level types that have a cargo feature of the same name
+pub const FAMILIES: [&str; 8] = [
+    "image",
+    "audio",
+    "video",
+    "application",
+    "text",
+    "font",
+    "model",
+    "message",
+];
+
+/// The feature that keeps every type, whatever its family
+pub const ALL_TYPES: &str = "all_types";
+
+/// Office documents, by the start of their type name
+const OFFICE: &[&str] = &[
+    "application/msword",
+    "application/pdf",
+    "application/rtf",
+    "application/vnd.apple.iwork",
+    "application/vnd.apple.keynote",
+    "application/vnd.apple.numbers",
+    "application/vnd.apple.pages",
+    "application/vnd.ms-excel",
+    "application/vnd.ms-outlook",
+    "application/vnd.ms-powerpoint",
+    "application/vnd.ms-project",
+    "application/vnd.ms-visio",
+    "application/vnd.ms-word",
+    "application/vnd.ms-works",
+    "application/vnd.oasis.opendocument.",
+    "application/vnd.openxmlformats-officedocument.",
+    "application/vnd.stardivision.",
+    "application/vnd.sun.xml.",
+    "application/vnd.visio",
+    "application/vnd.wordperfect",
+    "application/wordperfect",
+    "application/hwp+zip",
+    "application/x-hwp",
+    "application/x-mspublisher",
+    "application/x-staroffice-template",
+    "application/x-tika-iworks-protected",
+    "application/x-tika-msoffice",
+    "application/x-tika-old-excel",
+    "application/x-tika-ooxml",
+    "application/x-tika-staroffice",
+    "application/x-tika-visio-ooxml",
+];
+
+/// Archives and compressed files
+const ARCHIVES: &[&str] = &[
+    "application/gzip",
+    "application/java-archive",
+    "application/lzip",
+    "application/vnd.ms-cab-compressed",
+    "application/x-7z-compressed",
+    "application/x-ace-compressed",
+    "application/x-archive",
+    "application/x-arj",
+    "application/x-bcpio",
+    "application/x-brotli",
+    "application/x-bzip",
+    "application/x-bzip2",
+    "application/x-compress",
+    "application/x-cpio",
+    "application/x-debian-package",
+    "application/x-gtar",
+    "application/x-iso9660-image",
+    "application/x-lha",
+    "application/x-lharc",
+    "application/x-lz4",
+    "application/x-lzip",
+    "application/x-lzma",
+    "application/x-ms-compress-szdd",
+    "application/x-rar-compressed",
+    "application/x-rpm",
+    "application/x-snappy-framed",
+    "application/x-stuffit",
+    "application/x-stuffitx",
+    "application/x-sv4cpio",
+    "application/x-tar",
+    "application/x-uc2-compressed",
+    "application/x-ustar",
+    "application/x-xz",
+    "application/zip",
+    "application/zstd",
+];
+
+/// The features that keep a type: its family and the groups it is in
+pub fn tags(mime: &str) -> BTreeSet<&'static str> {
+    let family = mime.split('/').next().unwrap_or_default();
+    let mut tags = FAMILIES
+        .iter()
+        .copied()
+        .filter(|f| *f == family)
+        .collect::<BTreeSet<_>>();
+
+    if OFFICE.iter().any(|prefix| mime.starts_with(prefix)) {
+        tags.insert("office");
+    }
+    let base = mime.split(';').next().unwrap_or_default();
+    if ARCHIVES.contains(&base) {
+        tags.insert("archives");
+    }
+    tags
+}
+
+/// The features that keep each type, by its own tags and those of every type
+/// below it, so that a kept type can always walk up to its parents
+pub fn features(
+    tags: &[BTreeSet<&'static str>],
+    children: &[Vec<usize>],
+) -> Vec<BTreeSet<&'static str>> {
+    let mut features = tags.to_vec();
+    // The hierarchy is shallow, spreading until nothing changes is enough
+    loop {
+        let mut changed = false;
+        for (parent, children) in children.iter().enumerate() {
+            for child in children {
+                let missing = features[*child]
+                    .difference(&features[parent])
+                    .copied()
+                    .collect::<Vec<_>>();
+                changed |= !missing.is_empty();
+                features[parent].extend(missing);
+            }
+        }
+        if !changed {
+            return features;
+        }
+    }
+}
+
+/// The `cfg` predicate of a type kept by some features
+pub fn predicate(features: &BTreeSet<&'static str>) -> String {
+    let features = std::iter::once(ALL_TYPES)
+        .chain(features.iter().copied())
+        .map(|feature| format!("feature = \"{feature}\""))
+        .collect::<Vec<_>>();
+    match features.as_slice() {
+        [all_types] => all_types.clone(),
+        features => format!("any({})", features.join(", ")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tags() {
+        assert_eq!(tags("image/png"), BTreeSet::from(["image"]));
+        assert_eq!(
+            tags("application/vnd.ms-excel.sheet.4"),
+            BTreeSet::from(["application", "office"])
+        );
+        assert_eq!(
+            tags("application/x-rar-compressed;version=5"),
+            BTreeSet::from(["application", "archives"])
+        );
+        assert_eq!(tags("chemical/x-pdb"), BTreeSet::new());
+    }
+
+    #[test]
+    fn test_parents_are_kept() {
+        // text/plain <- application/xml <- image/svg+xml
+        let tags = [
+            BTreeSet::from(["text"]),
+            BTreeSet::from(["application"]),
+            BTreeSet::from(["image"]),
+        ];
+        let features = features(&tags, &[vec![1], vec![2], vec![]]);
+        assert_eq!(
+            features[0],
+            BTreeSet::from(["application", "image", "text"])
+        );
+        assert_eq!(features[2], BTreeSet::from(["image"]));
+        assert_eq!(
+            predicate(&features[2]),
+            "any(feature = \"all_types\", feature = \"image\")"
+        );
+        assert_eq!(predicate(&BTreeSet::new()), "feature = \"all_types\"");
+    }
+}
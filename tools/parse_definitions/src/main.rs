@@ -1,14 +1,23 @@
+mod dispatch;
+mod families;
+mod needle_pool;
 mod parse_xml;
+mod range_needles;
+mod regex_reach;
 mod rule_regexes;
 
+use crate::dispatch::{bytes_at, ByteSet, DispatchIndex, DISPATCH_OFFSETS};
+use crate::needle_pool::NeedlePool;
 use crate::parse_xml::{parse_mime_type_xml, Match, MimeType, Offset};
+use crate::range_needles::RangeNeedles;
+use crate::regex_reach::regex_reach;
 use crate::rule_regexes::RuleRegexes;
 use crate::MatchRule::{And, RootXML};
 use num_traits::Num;
 use regex::bytes::Regex;
 use sailfish::TemplateSimple;
 use std::cmp::{max, Ordering};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::env;
 use std::fs::File;
 use std::io::Write;
@@ -18,19 +27,40 @@ struct OutputMimeType {
     short_name: String,
     mime_string: String,
     globs: Vec<String>,
+    /// The patterns of `globs` that are regular expressions
+    regex_globs: Vec<String>,
+    aliases: Vec<String>,
+    description: Option<String>,
+    acronym: Option<String>,
+    uti: Option<String>,
+    links: Vec<String>,
     match_rules_string: String,
+    rule_data_string: String,
+    rule_table_string: String,
     subclasses: Vec<String>,
     priority: u32,
     children: Vec<String>,
+    /// The `cfg` predicate of the features that keep the type
+    cfg: String,
     regex_patterns: RuleRegexes,
+    dispatch: Vec<ByteSet>,
 }
 
-#[derive(TemplateSimple)]
+#[derive(Clone, TemplateSimple)]
 #[template(path = "generated.rs.stpl")]
 struct OutputTemplate {
     types: Vec<OutputMimeType>,
-    type_map: HashMap<String, Vec<String>>,
-    ext_map: HashMap<String, Vec<String>>,
+    type_map: BTreeMap<String, Vec<String>>,
+    ext_map: BTreeMap<String, Vec<String>>,
+    regex_globs: BTreeSet<String>,
+    /// How many bytes after its offset each regex with a bounded reach can look at
+    regex_reach: BTreeMap<String, usize>,
+    dispatch_offsets: Vec<u32>,
+    dispatch: DispatchIndex,
+    range_needles: RangeNeedles,
+    needle_pool: NeedlePool,
+    /// Whether to output the rule table rather than a `check_with()` per type
+    table: bool,
 }
 
 fn mime_to_short_name(mime_type: &str) -> String {
@@ -45,6 +75,11 @@ fn mime_to_short_name(mime_type: &str) -> String {
     format!("T_{short_name}")
 }
 
+/// Descriptions in the XML are often wrapped over several indented lines
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
 fn print_help() {
     println!("Usage: parse_definitions <XML_FILE_PATHS>");
     println!();
@@ -53,6 +88,12 @@ fn print_help() {
         "    <XML_FILE_PATHS>    The paths to the Apache Tika MIME type XML files to parse and generate code from."
     );
     println!();
+    println!("Output:");
+    println!("    generated.rs          A checker per type, copy it to src/magic/");
+    println!(
+        "    generated_table.rs    The same types as a rule table, for the `rule_table` feature"
+    );
+    println!();
     println!("Example:");
     println!("    parse_definitions tika-mimetypes.xml");
 }
@@ -182,6 +223,50 @@ fn string_to_bytes(input: &str) -> Vec<u8> {
     result
 }
 
+/// Makes the word boundaries of a pattern ASCII only. The lazy DFA gives up on a
+/// Unicode word boundary when it sees a non-ASCII byte, and the error it gives up
+/// with is allocated.
+///
+/// The definitions escape patterns for a Rust string literal, so a `\b` of the
+/// pattern is written `\\b`.
+fn ascii_word_boundaries(pattern: &str) -> String {
+    let mut translated = String::with_capacity(pattern.len());
+    let mut escaped = false;
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            translated.push(c);
+            escaped = false;
+            continue;
+        }
+
+        match chars.next() {
+            Some('\\') if !escaped => {
+                translated.push_str(r"\\");
+                escaped = true;
+            }
+            Some('\\') => {
+                translated.push_str(r"\\");
+                escaped = false;
+            }
+            Some(next) => {
+                translated.push(c);
+                translated.push(next);
+                escaped = false;
+            }
+            None => translated.push(c),
+        }
+
+        if escaped && chars.as_str().starts_with(['b', 'B']) {
+            let boundary = chars.next().unwrap();
+            translated.truncate(translated.len() - 2);
+            translated.push_str(&format!(r"(?-u:\\{boundary})"));
+            escaped = false;
+        }
+    }
+    translated
+}
+
 fn match_to_rule(mat: &Match, regex_patterns: &mut RuleRegexes) -> MatchRule {
     let rule = match &mat.match_type.as_str() {
         &"string" => match (&mat.offset, &mat.value, &mat.mask) {
@@ -237,7 +322,10 @@ fn match_to_rule(mat: &Match, regex_patterns: &mut RuleRegexes) -> MatchRule {
                 panic!("Unhandled stringignorecase rule: {:?}", &mat);
             }
         },
-        &"regex" => match (&mat.offset, &mat.value) {
+        &"regex" => match (
+            &mat.offset,
+            &mat.value.as_deref().map(ascii_word_boundaries),
+        ) {
             (Some(Offset::Start(start)), Some(value)) => {
                 // Test the regex pattern, we don't support some features
                 println!("Testing regex pattern: {value}");
@@ -373,7 +461,7 @@ fn actions_to_rules(mime: &MimeType) -> (MatchRule, RuleRegexes) {
     // Deduplicated cache of all regex patterns that appear in this mimetype from
     // the XML file. Used to create a regex compilation cache using `once_cell::sync::Lazy`
     // in the template.
-    let mut regex_patterns: RuleRegexes = RuleRegexes::default();
+    let mut regex_patterns = RuleRegexes::new(&mime_to_short_name(&mime.mime_type));
 
     for magic in &mime.magics {
         if magic.matches.len() == 1 {
@@ -399,12 +487,38 @@ fn actions_to_rules(mime: &MimeType) -> (MatchRule, RuleRegexes) {
     (rule, regex_patterns)
 }
 
-fn rules_to_string(match_rule: &MatchRule, regex_patterns: &RuleRegexes) -> String {
+/// The needle of a masked rule with the mask already applied, so the generated
+/// code compares masked input bytes to it as they are
+fn masked(needle: &[u8], mask: &[u8]) -> Vec<u8> {
+    needle
+        .iter()
+        .enumerate()
+        .map(|(i, n)| mask.get(i).map_or(*n, |m| n & m))
+        .collect()
+}
+
+/// The `rootxml_local_needles` of a root element name, as a slice of needles
+/// spelled by `needle`
+fn rootxml_needles(local: &str, needle: &mut dyn FnMut(&[u8]) -> String) -> String {
+    let needles = [
+        format!("<{local} "),
+        format!("{local}:{local} "),
+        format!(":{local} xmlns"),
+    ]
+    .map(|local_needle| needle(local_needle.as_bytes()));
+    format!("[{}]", needles.join(", "))
+}
+
+fn rules_to_string(
+    match_rule: &MatchRule,
+    regex_patterns: &RuleRegexes,
+    range_needles: &mut RangeNeedles,
+) -> String {
     match match_rule {
         MatchRule::Or(rules) => {
             let strings = rules
                 .iter()
-                .map(|match_rule| rules_to_string(match_rule, regex_patterns))
+                .map(|match_rule| rules_to_string(match_rule, regex_patterns, range_needles))
                 .collect::<Vec<String>>();
             let joined = strings.join(" || ");
             format!("({joined})")
@@ -412,7 +526,7 @@ fn rules_to_string(match_rule: &MatchRule, regex_patterns: &RuleRegexes) -> Stri
         MatchRule::And(rules) => {
             let strings = rules
                 .iter()
-                .map(|match_rule| rules_to_string(match_rule, regex_patterns))
+                .map(|match_rule| rules_to_string(match_rule, regex_patterns, range_needles))
                 .collect::<Vec<String>>();
             let joined = strings.join(" && ");
             format!("({joined})")
@@ -420,19 +534,29 @@ fn rules_to_string(match_rule: &MatchRule, regex_patterns: &RuleRegexes) -> Stri
         MatchRule::String(offset, bytes) => {
             format!("offset(bytes, {offset}, &{bytes:?})")
         }
+        MatchRule::StringRange(start, end, bytes) if !bytes.is_empty() => {
+            let needle = range_needles.insert(bytes, *end);
+            format!("offset_range_hit(bytes, hits, {start}, {end}, {needle})")
+        }
         MatchRule::StringRange(start, end, bytes) => {
             format!("offset_range(bytes, {start}, {end}, &{bytes:?})")
         }
         MatchRule::StringCaseInsensitive(offset, bytes) => {
             format!("offset_case_insensitive(bytes, {offset}, &{bytes:?})")
         }
+        MatchRule::StringRangeCaseInsensitive(start, end, bytes) if !bytes.is_empty() => {
+            let needle = range_needles.insert_case_insensitive(bytes, *end);
+            format!("offset_range_case_insensitive_hit(bytes, hits, {start}, {end}, {needle})")
+        }
         MatchRule::StringRangeCaseInsensitive(start, end, bytes) => {
             format!("offset_range_case_insensitive(bytes, {start}, {end}, &{bytes:?})")
         }
         MatchRule::StringMask(offset, bytes, mask) => {
+            let bytes = masked(bytes, mask);
             format!("offset_mask(bytes, {offset}, &{bytes:?}, &{mask:?})")
         }
         MatchRule::StringMaskRange(start, end, bytes, mask) => {
+            let bytes = masked(bytes, mask);
             format!("offset_mask_range(bytes, {start}, {end}, &{bytes:?}, &{mask:?})")
         }
         MatchRule::StringMaskCaseInsensitive(offset, bytes, mask) => {
@@ -444,17 +568,17 @@ fn rules_to_string(match_rule: &MatchRule, regex_patterns: &RuleRegexes) -> Stri
             )
         }
         MatchRule::Regex(offset, pattern) => {
-            let regex_index = regex_patterns
-                .get_index(pattern)
+            let regex_name = regex_patterns
+                .static_name(pattern)
                 .expect("Regex pattern exists in rule");
-            format!("regex(bytes, {offset}, &REGEX_PATTERN_{regex_index})")
+            format!("regex(bytes, {offset}, &{regex_name})")
         }
         MatchRule::RegexRange(start, end, pattern) => {
-            let regex_index = regex_patterns
-                .get_index(pattern)
+            let regex_name = regex_patterns
+                .static_name(pattern)
                 .expect("Regex pattern exists in rule");
 
-            format!("regex_range(bytes, {start}, {end}, &REGEX_PATTERN_{regex_index})")
+            format!("regex_range(bytes, hits, {start}, {end}, &{regex_name})")
         }
         MatchRule::ValueU32(offset, value) => {
             format!("offset(bytes, {offset}, &{value:?})")
@@ -467,13 +591,17 @@ fn rules_to_string(match_rule: &MatchRule, regex_patterns: &RuleRegexes) -> Stri
             format!("unicode_le(bytes, {offset}, &{bytes:?})")
         }
         MatchRule::UnicodeLERange(start, end, bytes) => {
-            format!("unicode_le_range(bytes, {start}, {end}, &{bytes:?})")
+            format!("unicode_le_range(bytes, hits, {start}, {end}, &{bytes:?})")
         }
         MatchRule::RootXML(Some(local), Some(namespace)) => {
-            format!("rootxml(bytes, \"{local}\", \"{namespace}\")")
+            let needles = rootxml_needles(local, &mut inline);
+            format!("rootxml(bytes, &{needles}, \"{namespace}\")")
         }
         MatchRule::RootXML(Some(local), None) => {
-            format!("rootxml_local(bytes, \"{local}\")")
+            format!(
+                "rootxml_local(bytes, &{})",
+                rootxml_needles(local, &mut inline)
+            )
         }
         MatchRule::RootXML(None, Some(namespace)) => {
             format!("rootxml_namespace(bytes, \"{namespace}\")")
@@ -484,10 +612,101 @@ fn rules_to_string(match_rule: &MatchRule, regex_patterns: &RuleRegexes) -> Stri
     }
 }
 
+/// Renders a rule as a static `Rule` value, so the generated code can describe why
+/// a type matched as well as check it.
+/// A needle as a slice literal, the way the generated `check_with()` and rules
+/// spell it
+fn inline(needle: &[u8]) -> String {
+    format!("&{needle:?}")
+}
+
+/// The rule as `Rule` data, with each needle spelled by `needle`
+fn rules_to_data(
+    match_rule: &MatchRule,
+    regex_patterns: &RuleRegexes,
+    range_needles: &mut RangeNeedles,
+    needle: &mut dyn FnMut(&[u8]) -> String,
+) -> String {
+    let mut join = |rules: &[MatchRule]| {
+        rules
+            .iter()
+            .map(|match_rule| rules_to_data(match_rule, regex_patterns, range_needles, needle))
+            .collect::<Vec<String>>()
+            .join(", ")
+    };
+
+    match match_rule {
+        MatchRule::Or(rules) => format!("Rule::Or(&[{}])", join(rules)),
+        MatchRule::And(rules) => format!("Rule::And(&[{}])", join(rules)),
+        MatchRule::String(offset, bytes) => format!("Rule::Offset({offset}, {})", needle(bytes)),
+        MatchRule::StringRange(start, end, bytes) => {
+            let index = range_needles.insert(bytes, *end);
+            let bytes = needle(bytes);
+            format!("Rule::OffsetRange({start}, {end}, {bytes}, {index})")
+        }
+        MatchRule::StringCaseInsensitive(offset, bytes) => {
+            format!("Rule::OffsetCaseInsensitive({offset}, {})", needle(bytes))
+        }
+        MatchRule::StringRangeCaseInsensitive(start, end, bytes) => {
+            let index = range_needles.insert_case_insensitive(bytes, *end);
+            let bytes = needle(bytes);
+            format!("Rule::OffsetRangeCaseInsensitive({start}, {end}, {bytes}, {index})")
+        }
+        MatchRule::StringMask(offset, bytes, mask) => {
+            let bytes = needle(&masked(bytes, mask));
+            let mask = needle(mask);
+            format!("Rule::OffsetMask({offset}, {bytes}, {mask})")
+        }
+        MatchRule::StringMaskRange(start, end, bytes, mask) => {
+            let bytes = needle(&masked(bytes, mask));
+            let mask = needle(mask);
+            format!("Rule::OffsetMaskRange({start}, {end}, {bytes}, {mask})")
+        }
+        MatchRule::Regex(offset, pattern) => {
+            let regex_name = regex_patterns
+                .static_name(pattern)
+                .expect("Regex pattern exists in rule");
+            format!("Rule::Regex({offset}, &{regex_name})")
+        }
+        MatchRule::RegexRange(start, end, pattern) => {
+            let regex_name = regex_patterns
+                .static_name(pattern)
+                .expect("Regex pattern exists in rule");
+            format!("Rule::RegexRange({start}, {end}, &{regex_name})")
+        }
+        MatchRule::ValueU32(offset, value) | MatchRule::ValueU16(offset, value) => {
+            format!("Rule::Offset({offset}, {})", needle(value))
+        }
+        MatchRule::Empty => "Rule::Never".to_string(),
+        MatchRule::UnicodeLE(offset, bytes) => {
+            format!("Rule::UnicodeLe({offset}, {})", needle(bytes))
+        }
+        MatchRule::UnicodeLERange(start, end, bytes) => {
+            format!("Rule::UnicodeLeRange({start}, {end}, {})", needle(bytes))
+        }
+        MatchRule::RootXML(Some(local), Some(namespace)) => {
+            let needles = rootxml_needles(local, needle);
+            format!("Rule::RootXml(Some(\"{local}\"), Some(\"{namespace}\"), &{needles})")
+        }
+        MatchRule::RootXML(Some(local), None) => {
+            let needles = rootxml_needles(local, needle);
+            format!("Rule::RootXml(Some(\"{local}\"), None, &{needles})")
+        }
+        MatchRule::RootXML(None, Some(namespace)) => {
+            format!("Rule::RootXml(None, Some(\"{namespace}\"), &[])")
+        }
+        _ => {
+            panic!("Unsupported rule {match_rule:?}");
+        }
+    }
+}
+
 type AliasType = (String, String);
 
 fn parse_definition_file(
     xml_path: &str,
+    range_needles: &mut RangeNeedles,
+    needle_pool: &mut NeedlePool,
 ) -> Result<(Vec<OutputMimeType>, Vec<AliasType>), Box<dyn std::error::Error>> {
     let rules = parse_mime_type_xml(xml_path)?;
     let mime_types = rules.mime_types;
@@ -505,7 +724,34 @@ fn parse_definition_file(
                     .iter()
                     .filter_map(|z| z.pattern.clone())
                     .collect(),
-                match_rules_string: rules_to_string(&rules, &regex_patterns),
+                regex_globs: mime
+                    .globs
+                    .iter()
+                    .filter(|z| z.is_regex)
+                    .filter_map(|z| z.pattern.clone())
+                    .collect(),
+                aliases: mime
+                    .aliases
+                    .iter()
+                    .filter_map(|a| a.alias_type.clone())
+                    .collect(),
+                description: mime.comments.first().map(|c| collapse_whitespace(c)),
+                acronym: mime.acronyms.first().map(|a| collapse_whitespace(a)),
+                uti: mime.utis.first().map(|u| collapse_whitespace(u)),
+                links: mime.links.iter().map(|l| collapse_whitespace(l)).collect(),
+                match_rules_string: rules_to_string(&rules, &regex_patterns, range_needles),
+                rule_data_string: rules_to_data(
+                    &rules,
+                    &regex_patterns,
+                    range_needles,
+                    &mut inline,
+                ),
+                rule_table_string: rules_to_data(
+                    &rules,
+                    &regex_patterns,
+                    range_needles,
+                    &mut |needle: &[u8]| needle_pool.needle(needle),
+                ),
                 subclasses: mime
                     .sub_classes
                     .iter()
@@ -513,7 +759,12 @@ fn parse_definition_file(
                     .collect::<Vec<String>>(),
                 priority: max(mime.magics.iter().map(|m| m.priority).max().unwrap_or(0), 0),
                 children: vec![],
+                cfg: String::new(),
                 regex_patterns,
+                dispatch: DISPATCH_OFFSETS
+                    .iter()
+                    .map(|offset| bytes_at(&rules, *offset))
+                    .collect(),
             }
         })
         .collect::<Vec<OutputMimeType>>();
@@ -544,11 +795,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let mut output_mime_types = vec![];
     let mut alias_types = vec![];
+    let mut range_needles = RangeNeedles::default();
+    let mut needle_pool = NeedlePool::default();
 
     let xml_paths = &args[1..];
     for xml_path in xml_paths {
         println!("Processing file: {xml_path}");
-        let (omt, at) = parse_definition_file(xml_path)?;
+        let (omt, at) = parse_definition_file(xml_path, &mut range_needles, &mut needle_pool)?;
         output_mime_types.extend(omt);
         alias_types.extend(at);
     }
@@ -573,8 +826,17 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         )));
     }
 
-    let mut type_map: HashMap<String, Vec<String>> = HashMap::new();
-    let mut ext_map: HashMap<String, Vec<String>> = HashMap::new();
+    let mut type_map: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut ext_map: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let regex_globs: BTreeSet<String> = output_mime_types
+        .iter()
+        .flat_map(|mime| mime.regex_globs.iter().cloned())
+        .collect();
+    let regex_reach: BTreeMap<String, usize> = output_mime_types
+        .iter()
+        .flat_map(|mime| mime.regex_patterns.cloned_iter())
+        .filter_map(|(_, pattern)| Some((pattern.clone(), regex_reach(&pattern)?)))
+        .collect();
 
     // Add children, these should be types that subclass you and are *more specific*.
     let mut children_map: HashMap<String, Vec<String>> = HashMap::new();
@@ -624,16 +886,71 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             .push(format!("&{short_name}"));
     }
 
+    // Index the sorted types by the bytes their subtree can match at fixed offsets
+    let positions: HashMap<String, usize> = output_mime_types
+        .iter()
+        .enumerate()
+        .map(|(i, mime)| (format!("&{}", mime.short_name), i))
+        .collect();
+    let rules = output_mime_types
+        .iter()
+        .map(|mime| mime.dispatch.clone())
+        .collect::<Vec<_>>();
+    let children = output_mime_types
+        .iter()
+        .map(|mime| mime.children.iter().map(|c| positions[c]).collect())
+        .collect::<Vec<_>>();
+    let dispatch = DispatchIndex::new(&rules, &children);
+
+    // Gate each type and every reference to it behind the features that keep it
+    let tags = output_mime_types
+        .iter()
+        .map(|mime| families::tags(&mime.mime_string))
+        .collect::<Vec<_>>();
+    let features = families::features(&tags, &children);
+    let cfgs: HashMap<String, String> = output_mime_types
+        .iter()
+        .zip(&features)
+        .map(|(mime, features)| {
+            (
+                format!("&{}", mime.short_name),
+                families::predicate(features),
+            )
+        })
+        .collect();
+    let gate = |reference: &String| format!("#[cfg({})] {reference}", cfgs[reference]);
+    for mime in &mut output_mime_types {
+        mime.cfg = cfgs[&format!("&{}", mime.short_name)].clone();
+        mime.children = mime.children.iter().map(gate).collect();
+    }
+    for references in type_map.values_mut().chain(ext_map.values_mut()) {
+        *references = references.iter().map(gate).collect();
+    }
+
     let ctx = OutputTemplate {
         types: output_mime_types.to_vec(),
         type_map,
         ext_map,
+        regex_globs,
+        regex_reach,
+        dispatch_offsets: DISPATCH_OFFSETS.to_vec(),
+        dispatch,
+        range_needles,
+        needle_pool,
+        table: false,
     };
 
-    let code = ctx.render_once().unwrap();
-    File::create("generated.rs")?.write_all(code.as_bytes())?;
+    for (table, path) in [(false, "generated.rs"), (true, "generated_table.rs")] {
+        let code = OutputTemplate {
+            table,
+            ..ctx.clone()
+        }
+        .render_once()
+        .unwrap();
+        File::create(path)?.write_all(code.as_bytes())?;
 
-    println!("Generated Rust code written to `generated.rs`");
+        println!("Generated Rust code written to `{path}`");
+    }
 
     Ok(())
 }
@@ -687,11 +1004,22 @@ mod tests {
             MatchRule::String(0, [0x50, 0x4B, 0x07, 0x08].to_vec())
         );
 
-        let string_rules = rules_to_string(&rule, &regex_patterns);
+        let string_rules = rules_to_string(&rule, &regex_patterns, &mut RangeNeedles::default());
         assert_eq!(
             string_rules,
             "(offset(bytes, 0, &[80, 75, 3, 4]) || offset(bytes, 0, &[80, 75, 5, 6]) || offset(bytes, 0, &[80, 75, 7, 8]))"
         );
+
+        let rule_data = rules_to_data(
+            &rule,
+            &regex_patterns,
+            &mut RangeNeedles::default(),
+            &mut inline,
+        );
+        assert_eq!(
+            rule_data,
+            "Rule::Or(&[Rule::Offset(0, &[80, 75, 3, 4]), Rule::Offset(0, &[80, 75, 5, 6]), Rule::Offset(0, &[80, 75, 7, 8])])"
+        );
     }
 
     #[test]
@@ -742,7 +1070,7 @@ mod tests {
             MatchRule::String(8, [174, 177, 83, 120, 208, 41, 150, 212].to_vec())
         );
 
-        let string_rules = rules_to_string(&rule, &regex_patterns);
+        let string_rules = rules_to_string(&rule, &regex_patterns, &mut RangeNeedles::default());
         assert_eq!(
             string_rules,
             "(offset(bytes, 0, &[228, 82, 92, 123]) && offset(bytes, 4, &[140, 216]) && offset(bytes, 6, &[167, 77]) && (offset(bytes, 8, &[174, 177, 83, 120, 208, 41, 150, 211]) || offset(bytes, 8, &[174, 177, 83, 120, 208, 41, 150, 212])))"
@@ -768,7 +1096,7 @@ mod tests {
         let (rule, regex_patterns) = actions_to_rules(&mime);
         dbg!(&rule);
 
-        let string_rules = rules_to_string(&rule, &regex_patterns);
+        let string_rules = rules_to_string(&rule, &regex_patterns, &mut RangeNeedles::default());
         assert_eq!(
             string_rules,
             "(T_zip_application{}.check(bytes) && T_zip2_application{}.check(bytes) && offset(bytes, 0, &[80, 75, 3, 4]) && offset(bytes, 30, &[109, 105, 109, 101, 116, 121, 112, 101, 97, 112, 112, 108, 105, 99, 97, 116, 105, 111, 110, 47, 118, 110, 100, 46, 101, 116, 115, 105, 46, 97, 115, 105, 99, 45, 101, 43, 122, 105, 112]))"
@@ -811,7 +1139,7 @@ mod tests {
         let (rule, regex_patterns) = actions_to_rules(&mime);
         dbg!(&rule);
 
-        let string_rules = rules_to_string(&rule, &regex_patterns);
+        let string_rules = rules_to_string(&rule, &regex_patterns, &mut RangeNeedles::default());
         assert_eq!(
             string_rules,
             "(offset(bytes, 0, &[60, 63, 120, 109, 108]) || offset(bytes, 0, &[60, 63, 88, 77, 76]) || offset(bytes, 0, &[239, 187, 191, 60, 63, 120, 109, 108]) || offset(bytes, 0, &[255, 254, 60, 0, 63, 0, 120, 0, 109, 0, 108, 0]) || offset(bytes, 0, &[254, 255, 0, 60, 0, 63, 0, 120, 0, 109, 0, 108]) || offset(bytes, 0, &[60, 33, 45, 45]))"
@@ -832,10 +1160,123 @@ mod tests {
         let (rule, regex_patterns) = actions_to_rules(&mime);
         dbg!(&rule);
 
-        let string_rules = rules_to_string(&rule, &regex_patterns);
+        let string_rules = rules_to_string(&rule, &regex_patterns, &mut RangeNeedles::default());
         assert_eq!(
             string_rules,
-            r#"(rootxml(bytes, "html", "http://www.w3.org/1999/xhtml") || rootxml_local(bytes, "html") || rootxml_namespace(bytes, "http://www.w3.org/1991/xhtml"))"#
+            format!(
+                r#"(rootxml(bytes, &{html}, "http://www.w3.org/1999/xhtml") || rootxml_local(bytes, &{html}) || rootxml_namespace(bytes, "http://www.w3.org/1991/xhtml"))"#,
+                html = rootxml_needles("html", &mut inline)
+            )
+        );
+
+        let rule_data = rules_to_data(
+            &rule,
+            &regex_patterns,
+            &mut RangeNeedles::default(),
+            &mut inline,
+        );
+        assert_eq!(
+            rule_data,
+            format!(
+                r#"Rule::Or(&[Rule::RootXml(Some("html"), Some("http://www.w3.org/1999/xhtml"), &{html}), Rule::RootXml(Some("html"), None, &{html}), Rule::RootXml(None, Some("http://www.w3.org/1991/xhtml"), &[])])"#,
+                html = rootxml_needles("html", &mut inline)
+            )
+        );
+
+        assert_eq!(
+            rootxml_needles("a", &mut inline),
+            "[&[60, 97, 32], &[97, 58, 97, 32], &[58, 97, 32, 120, 109, 108, 110, 115]]"
+        );
+    }
+
+    #[test]
+    fn we_make_word_boundaries_ascii() {
+        assert_eq!(
+            ascii_word_boundaries(r"(?s)^\\s*(?:di)?graph\\b"),
+            r"(?s)^\\s*(?:di)?graph(?-u:\\b)"
+        );
+        assert_eq!(
+            ascii_word_boundaries(r"\\Bx\\\\b\n\b"),
+            r"(?-u:\\B)x\\\\b\n\b"
+        );
+    }
+
+    #[test]
+    fn we_mask_needles_of_mask_rules() {
+        let rule = MatchRule::StringMask(0, vec![0x1F, 0xFF], vec![0xF0]);
+        let regex_patterns = RuleRegexes::default();
+
+        let string_rules = rules_to_string(&rule, &regex_patterns, &mut RangeNeedles::default());
+        assert_eq!(string_rules, "offset_mask(bytes, 0, &[16, 255], &[240])");
+
+        let rule_data = rules_to_data(
+            &rule,
+            &regex_patterns,
+            &mut RangeNeedles::default(),
+            &mut inline,
+        );
+        assert_eq!(rule_data, "Rule::OffsetMask(0, &[16, 255], &[240])");
+    }
+
+    #[test]
+    fn we_share_regexes_between_check_and_rule() {
+        let def = r#"<mime-type type="text/x-matlab">
+    <magic priority="50">
+      <match value="^%.*" type="regex" offset="0"/>
+      <match value="function [a-zA-Z]" type="regex" offset="0:100"/>
+    </magic>
+  </mime-type>"#;
+
+        let mime: MimeType = from_str(def).unwrap();
+
+        let (rule, regex_patterns) = actions_to_rules(&mime);
+
+        let string_rules = rules_to_string(&rule, &regex_patterns, &mut RangeNeedles::default());
+        assert_eq!(
+            string_rules,
+            "(regex(bytes, 0, &T_X_MATLAB_TEXT_REGEX_0) || regex_range(bytes, hits, 0, 100, &T_X_MATLAB_TEXT_REGEX_1))"
+        );
+
+        let rule_data = rules_to_data(
+            &rule,
+            &regex_patterns,
+            &mut RangeNeedles::default(),
+            &mut inline,
+        );
+        assert_eq!(
+            rule_data,
+            "Rule::Or(&[Rule::Regex(0, &T_X_MATLAB_TEXT_REGEX_0), Rule::RegexRange(0, 100, &T_X_MATLAB_TEXT_REGEX_1)])"
+        );
+    }
+
+    #[test]
+    fn we_share_range_needles_between_types() {
+        let mut range_needles = RangeNeedles::default();
+        let regex_patterns = RuleRegexes::new("T_TEST");
+
+        let tmx = MatchRule::StringRange(0, 256, b"<tmx".to_vec());
+        let xliff = MatchRule::Or(vec![
+            MatchRule::StringRange(0, 1024, b"<xliff".to_vec()),
+            MatchRule::StringRange(64, 2048, b"<tmx".to_vec()),
+            MatchRule::StringRangeCaseInsensitive(0, 512, b"<XLIFF".to_vec()),
+        ]);
+
+        assert_eq!(
+            rules_to_string(&tmx, &regex_patterns, &mut range_needles),
+            "offset_range_hit(bytes, hits, 0, 256, 0)"
+        );
+        assert_eq!(
+            rules_to_string(&xliff, &regex_patterns, &mut range_needles),
+            "(offset_range_hit(bytes, hits, 0, 1024, 1) || offset_range_hit(bytes, hits, 64, 2048, 0) || offset_range_case_insensitive_hit(bytes, hits, 0, 512, 0))"
+        );
+        assert_eq!(
+            range_needles.needles(),
+            [b"<tmx".to_vec(), b"<xliff".to_vec()]
+        );
+        assert_eq!(
+            range_needles.case_insensitive_needles(),
+            [b"<xliff".to_vec()]
         );
+        assert_eq!(range_needles.head_len(), 2049);
     }
 }
@@ -0,0 +1,58 @@
+use std::collections::HashMap;
+
+#[derive(Clone, Default)]
+/// The needles of the rule table, all kept in one byte array.
+/// A needle found in the pool already, even within a longer one, is not added
+/// again. The rule table slices its needles out of the pool with `needle()`.
+pub struct NeedlePool {
+    bytes: Vec<u8>,
+    starts: HashMap<Vec<u8>, usize>,
+}
+
+impl NeedlePool {
+    /// Where the needle starts in the pool
+    pub fn insert(&mut self, needle: &[u8]) -> usize {
+        if needle.is_empty() {
+            return 0;
+        }
+        if let Some(start) = self.starts.get(needle) {
+            return *start;
+        }
+
+        let start = match self.bytes.windows(needle.len()).position(|w| w == needle) {
+            Some(start) => start,
+            None => {
+                self.bytes.extend_from_slice(needle);
+                self.bytes.len() - needle.len()
+            }
+        };
+        self.starts.insert(needle.to_vec(), start);
+        start
+    }
+
+    /// The expression the rule table gets the needle with
+    pub fn needle(&mut self, needle: &[u8]) -> String {
+        format!("needle({}, {})", self.insert(needle), needle.len())
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_needles_are_shared() {
+        let mut pool = NeedlePool::default();
+        assert_eq!(pool.insert(b"PK\x03\x04"), 0);
+        assert_eq!(pool.insert(b"%PDF-"), 4);
+        assert_eq!(pool.insert(b"PDF"), 5);
+        assert_eq!(pool.insert(b"PK\x03\x04"), 0);
+        assert_eq!(pool.insert(b""), 0);
+        assert_eq!(pool.bytes(), b"PK\x03\x04%PDF-");
+        assert_eq!(pool.needle(b"\x03\x04"), "needle(2, 2)");
+    }
+}
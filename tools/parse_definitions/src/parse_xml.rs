@@ -26,6 +26,10 @@ pub struct MimeType {
     pub aliases: Vec<Alias>,
     pub sub_classes: Vec<SubClass>,
     pub root_xml: Vec<RootXml>,
+    pub comments: Vec<String>,
+    pub acronyms: Vec<String>,
+    pub links: Vec<String>,
+    pub utis: Vec<String>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -33,6 +37,9 @@ pub struct MimeType {
 pub struct Glob {
     #[serde(rename = "@pattern")]
     pub pattern: Option<String>,
+    /// Whether the pattern is a regular expression rather than a glob
+    #[serde(rename = "@isregex", default)]
+    pub is_regex: bool,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -115,6 +122,10 @@ impl<'de> Deserialize<'de> for MimeType {
                 let mut aliases = Vec::new();
                 let mut sub_classes = Vec::new();
                 let mut root_xml = Vec::new();
+                let mut comments = Vec::new();
+                let mut acronyms = Vec::new();
+                let mut links = Vec::new();
+                let mut utis = Vec::new();
 
                 while let Some(key) = map.next_key::<String>()? {
                     match key.as_str() {
@@ -136,6 +147,19 @@ impl<'de> Deserialize<'de> for MimeType {
                         "root-XML" => {
                             root_xml.push(map.next_value()?);
                         }
+                        "_comment" => {
+                            comments.push(map.next_value()?);
+                        }
+                        "acronym" => {
+                            acronyms.push(map.next_value()?);
+                        }
+                        // quick-xml drops the namespace prefix of <tika:link> and <tika:uti>
+                        "link" => {
+                            links.push(map.next_value()?);
+                        }
+                        "uti" => {
+                            utis.push(map.next_value()?);
+                        }
                         _ => {
                             // just eat the rest
                             let _: String = map.next_value()?;
@@ -150,6 +174,10 @@ impl<'de> Deserialize<'de> for MimeType {
                     aliases,
                     sub_classes,
                     root_xml,
+                    comments,
+                    acronyms,
+                    links,
+                    utis,
                 })
             }
         }
@@ -244,6 +272,15 @@ mod tests {
         let globs = &mime_types.mime_types[0].globs;
         assert_eq!(globs[0].pattern, Some("*.zip".to_string()));
         assert_eq!(globs[1].pattern, Some("*.zipx".to_string()));
+
+        let mime = &mime_types.mime_types[0];
+        assert_eq!(mime.comments, vec!["Compressed Archive File"]);
+        assert_eq!(
+            mime.links,
+            vec!["http://en.wikipedia.org/wiki/ZIP_(file_format)"]
+        );
+        assert_eq!(mime.utis, vec!["com.pkware.zip-archive"]);
+        assert!(mime.acronyms.is_empty());
     }
 
     #[test]
@@ -355,6 +392,9 @@ mod tests {
 
         let mime: MimeType = from_str(def).unwrap();
         assert_eq!(mime.mime_type, "text/html");
+        assert_eq!(mime.comments, vec!["HyperText Markup Language"]);
+        assert_eq!(mime.acronyms, vec!["HTML"]);
+        assert_eq!(mime.utis, vec!["public.html"]);
 
         let magic = &mime.magics;
         assert_eq!(magic.len(), 3);
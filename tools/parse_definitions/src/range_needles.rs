@@ -0,0 +1,50 @@
+#[derive(Clone, Default)]
+/// A deduplicated list of the needles of all the range rules.
+/// The generated `RangeHits` looks for every needle in a single pass over the
+/// head of the input, and the generated `check()` looks up where a needle first
+/// occurs by its index here, instead of scanning the range for it.
+pub struct RangeNeedles {
+    needles: Vec<Vec<u8>>,
+    case_insensitive_needles: Vec<Vec<u8>>,
+    head_len: u32,
+}
+
+impl RangeNeedles {
+    /// The index of a needle looked for up to `end`, inclusive
+    pub fn insert(&mut self, needle: &[u8], end: u32) -> usize {
+        self.head_len = self.head_len.max(end.saturating_add(1));
+        Self::index(&mut self.needles, needle.to_vec())
+    }
+
+    /// Like [`insert`](Self::insert), for a needle matched ignoring ASCII case
+    pub fn insert_case_insensitive(&mut self, needle: &[u8], end: u32) -> usize {
+        self.head_len = self.head_len.max(end.saturating_add(1));
+        Self::index(
+            &mut self.case_insensitive_needles,
+            needle.to_ascii_lowercase(),
+        )
+    }
+
+    fn index(needles: &mut Vec<Vec<u8>>, needle: Vec<u8>) -> usize {
+        match needles.iter().position(|n| *n == needle) {
+            Some(index) => index,
+            None => {
+                needles.push(needle);
+                needles.len() - 1
+            }
+        }
+    }
+
+    pub fn needles(&self) -> &[Vec<u8>] {
+        &self.needles
+    }
+
+    pub fn case_insensitive_needles(&self) -> &[Vec<u8>] {
+        &self.case_insensitive_needles
+    }
+
+    /// How much of the input the range rules look at
+    pub fn head_len(&self) -> u32 {
+        self.head_len
+    }
+}
@@ -0,0 +1,81 @@
+use regex_syntax::hir::Look;
+use regex_syntax::ParserBuilder;
+
+/// How many bytes after its offset a regex rule can look at, None if it can match
+/// anywhere after it.
+///
+/// The definitions escape patterns for a Rust string literal, so the pattern is
+/// unescaped first, and parsed like `regex::bytes::Regex` parses it. Only a pattern
+/// anchored at the start with a bounded length has a reach.
+pub fn regex_reach(literal: &str) -> Option<usize> {
+    let pattern = unescape(literal)?;
+    let hir = ParserBuilder::new()
+        .utf8(false)
+        .build()
+        .parse(&pattern)
+        .ok()?;
+    let properties = hir.properties();
+    if !properties.look_set_prefix().contains(Look::Start) {
+        return None;
+    }
+
+    properties.maximum_len()
+}
+
+/// The string a Rust string literal stands for, None for an escape it can't have
+fn unescape(literal: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(literal.len());
+    let mut chars = literal.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+
+        let c = match chars.next()? {
+            '\\' => '\\',
+            '"' => '"',
+            '\'' => '\'',
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            '0' => '\0',
+            'x' => {
+                let hex = chars.by_ref().take(2).collect::<String>();
+                char::from(u8::from_str_radix(&hex, 16).ok().filter(u8::is_ascii)?)
+            }
+            'u' => {
+                let rest = chars.as_str().strip_prefix('{')?;
+                let (hex, rest) = rest.split_once('}')?;
+                let c = char::from_u32(u32::from_str_radix(hex, 16).ok()?)?;
+                chars = rest.chars();
+                c
+            }
+            _ => return None,
+        };
+        unescaped.push(c);
+    }
+
+    Some(unescaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_regex_reach() {
+        assert_eq!(regex_reach("^begin [0-9]{3} "), Some(10));
+        assert_eq!(regex_reach("(?s)^\\\\x44\\\\x43.\\\\x01"), Some(7));
+        assert_eq!(regex_reach("BZh[1-9]"), None);
+        assert_eq!(regex_reach("^[ -~]{6}[ -~]+"), None);
+        assert_eq!(regex_reach("(?m)^a"), None);
+    }
+
+    #[test]
+    fn test_unescape() {
+        assert_eq!(unescape(r#"a\\b\"\x41\u{e9}"#).as_deref(), Some("a\\b\"Aé"));
+        assert_eq!(unescape(r"\q"), None);
+        assert_eq!(unescape(r"\xff"), None);
+    }
+}
@@ -1,23 +1,43 @@
 #[derive(Default, Clone)]
 /// A deduplicated cache of all the regexes found in a rule.
-/// This is used to create the per-rule `once_cell::sync::Lazy` which
-/// caches the compilation of the regex string
+/// This is used to create the per-rule `once_cell::sync::Lazy` statics which
+/// cache the compilation of the regex string. The statics are shared by the
+/// generated `check()` and `get_rule()`, so they are named after the type.
 ///
 /// Not the most efficient implementation but doesn't require any dependencies
-pub struct RuleRegexes(Vec<String>);
+pub struct RuleRegexes {
+    prefix: String,
+    patterns: Vec<String>,
+}
 
 impl RuleRegexes {
+    pub fn new(short_name: &str) -> Self {
+        RuleRegexes {
+            prefix: short_name.to_uppercase(),
+            patterns: vec![],
+        }
+    }
+
     pub fn insert(&mut self, value: &str) {
-        if !self.0.iter().any(|v| v == value) {
-            self.0.push(value.to_owned());
+        if !self.patterns.iter().any(|v| v == value) {
+            self.patterns.push(value.to_owned());
         }
     }
 
     pub fn get_index(&self, value: &str) -> Option<usize> {
-        self.0.iter().position(|v| v == value)
+        self.patterns.iter().position(|v| v == value)
+    }
+
+    /// The name of the static holding the compiled pattern
+    pub fn static_name(&self, value: &str) -> Option<String> {
+        self.get_index(value)
+            .map(|index| format!("{}_REGEX_{index}", self.prefix))
     }
 
-    pub fn cloned_iter(&self) -> impl Iterator<Item = (usize, String)> + '_ {
-        self.0.iter().cloned().enumerate()
+    pub fn cloned_iter(&self) -> impl Iterator<Item = (String, String)> + '_ {
+        self.patterns
+            .iter()
+            .enumerate()
+            .map(|(index, pattern)| (format!("{}_REGEX_{index}", self.prefix), pattern.clone()))
     }
 }